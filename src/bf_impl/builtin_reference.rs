@@ -0,0 +1,76 @@
+use crate::*;
+
+#[derive(Clone)]
+pub struct BuiltinReferenceBfImpl;
+
+lazy_static::lazy_static! {
+    /// Name of the interpreter. Often a github repo or website name.
+    static ref NAME: String = String::from("brainfuck-benchmark/reference");
+    /// Source Website
+    static ref WEBSITE: String = String::from("https://github.com/cwfitzgerald/brainfuck-benchmark");
+    /// Actual EXE ran. This is the benchmark harness itself.
+    static ref RESULT_EXE: String = std::env::current_exe().unwrap().to_string_lossy().to_string();
+}
+
+impl BFImpl for BuiltinReferenceBfImpl {
+    fn name(&self) -> String {
+        NAME.clone()
+    }
+
+    fn interpreted(&self) -> bool {
+        true
+    }
+
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn get(&self) -> StageResult {
+        Ok(())
+    }
+
+    fn update(&self) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    fn revision(&self) -> Option<String> {
+        None
+    }
+
+    fn requirements(&self) -> Vec<Requirement> {
+        Vec::new()
+    }
+
+    fn build_recipe(&self) -> String {
+        String::new()
+    }
+
+    fn build(&self) -> StageResult {
+        Ok(())
+    }
+
+    fn prepare(&self, _file: PathBuf) -> StageResult {
+        Ok(())
+    }
+
+    fn compiled_exe(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn generated_sources(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    fn cached_exe(&self, _file: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    fn get_invoke_command(&self, file: PathBuf) -> String {
+        let file_str = file.to_string_lossy().to_string();
+        format!("{} --interpret {}", &*RESULT_EXE, file_str)
+    }
+
+    fn website(&self) -> String {
+        WEBSITE.clone()
+    }
+}
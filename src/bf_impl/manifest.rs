@@ -0,0 +1,522 @@
+use crate::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Contents of the implementation manifest, `bf-impls.toml` by default
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(rename = "impl", default)]
+    impls: Vec<ManifestBfImpl>,
+}
+
+/// Where the source or binary of an implementation comes from. When several are given the first
+/// of `path`, `tarball`, `mirror`, `git` and `download` is used.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Source {
+    /// Git repository cloned into the source folder
+    git: Option<String>,
+    /// Branch, tag or commit to check out instead of following master
+    branch: Option<String>,
+    /// Local bare git mirror used instead of `git`
+    mirror: Option<String>,
+    /// Local tarball extracted into the source folder
+    tarball: Option<String>,
+    /// Leading path components stripped from the tarball, like tar's `--strip-components`
+    #[serde(default)]
+    strip: usize,
+    /// Local folder copied into the source folder, or a prebuilt binary copied to the executable path
+    path: Option<String>,
+    /// Build the local folder where it is instead of copying it
+    #[serde(default)]
+    in_place: bool,
+    /// Prebuilt binary downloaded to the executable path
+    download: Option<String>,
+    /// Expected SHA-256 of the downloaded binary, tarball or local binary
+    sha256: Option<String>,
+}
+
+impl Source {
+    /// Fields set in `over` win, everything else comes from `self`.
+    fn merge(&self, over: &Source) -> Source {
+        Source {
+            git: over.git.clone().or_else(|| self.git.clone()),
+            branch: over.branch.clone().or_else(|| self.branch.clone()),
+            mirror: over.mirror.clone().or_else(|| self.mirror.clone()),
+            tarball: over.tarball.clone().or_else(|| self.tarball.clone()),
+            strip: if over.tarball.is_some() {
+                over.strip
+            } else {
+                self.strip
+            },
+            path: over.path.clone().or_else(|| self.path.clone()),
+            in_place: if over.path.is_some() {
+                over.in_place
+            } else {
+                self.in_place
+            },
+            download: over.download.clone().or_else(|| self.download.clone()),
+            // A file from the override brings its own hash
+            sha256: if over.tarball.is_some() || over.download.is_some() || over.path.is_some() {
+                over.sha256.clone()
+            } else {
+                self.sha256.clone()
+            },
+        }
+    }
+
+    /// Downloads and tarballs must have a sha256, unless a local path replaces them.
+    fn check_sha256(&self) -> Result<(), String> {
+        if self.path.is_none() && self.sha256.is_none() {
+            if let Some(file) = self.tarball.as_ref().or_else(|| self.download.as_ref()) {
+                return Err(format!("{} has no sha256", file));
+            }
+        }
+        Ok(())
+    }
+
+    /// Local or downloaded file the source comes from, as opposed to a folder or git repo
+    fn artifact(&self) -> Option<&String> {
+        match &self.path {
+            Some(path) if Path::new(path).is_file() => Some(path),
+            Some(_) => None,
+            None => self.tarball.as_ref(),
+        }
+    }
+
+    /// Git url to clone and fetch from, if the source is a git repo
+    fn git_url(&self) -> Option<&String> {
+        if self.path.is_some() || self.tarball.is_some() {
+            None
+        } else {
+            self.mirror.as_ref().or_else(|| self.git.as_ref())
+        }
+    }
+}
+
+/// Contents of `bf-sources.toml`, which replaces where implementations come from on this machine.
+/// Useful for machines without network access.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceOverrides {
+    /// Source fields to override, by implementation name
+    #[serde(default)]
+    source: BTreeMap<String, Source>,
+}
+
+impl SourceOverrides {
+    /// Loads the overrides, or no overrides if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(SourceOverrides::default());
+        }
+        let text =
+            read_to_string(path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+    }
+}
+
+/// One step of a build or prepare recipe. Exactly one of `run` and `cmake` must be given.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    /// Shell command to run
+    run: Option<String>,
+    /// Write the standard output of `run` to this file
+    stdout: Option<String>,
+    /// Name of a CMake target to configure and build into the out folder
+    cmake: Option<String>,
+    /// Glob of sources to generate a CMakeLists.txt from, if the project doesn't have one
+    sources: Option<String>,
+    /// Folder the command runs in, or the CMake source folder. Defaults to the source folder.
+    dir: Option<String>,
+    /// Platforms the step runs on. Empty means all of them.
+    #[serde(default)]
+    platforms: Vec<String>,
+    /// Tools the step needs
+    #[serde(default)]
+    requires: Vec<Requirement>,
+}
+
+/// An implementation described by the manifest.
+///
+/// Strings in steps, `exe`, `invoke` and `generated` may use the placeholders `{root}` (the
+/// benchmark folder), `{src}`, `{out}`, `{exe}`, and in the prepare stage and `invoke`, `{file}`
+/// and `{stem}` for the benchmark being run.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestBfImpl {
+    /// Name of the implementation. Often a github repo or website name.
+    name: String,
+    /// Source website
+    website: String,
+    /// If it is an interpreter, otherwise it is a compiler which produces `exe` in the prepare stage
+    interpreted: bool,
+    /// Platforms the implementation runs on: linux, macos, windows or unix. Empty means all of them.
+    #[serde(default)]
+    platforms: Vec<String>,
+    /// Tools the implementation needs, such as `make` or `cmake >= 3.12`. The tools for its source
+    /// and for CMake steps are added automatically.
+    #[serde(default)]
+    requires: Vec<Requirement>,
+    /// Source folder
+    src: String,
+    /// Folder in the out folder for temporaries
+    out: String,
+    /// Actual EXE ran
+    exe: String,
+    #[serde(default)]
+    source: Source,
+    /// Steps for building the implementation itself
+    #[serde(default)]
+    build: Vec<Step>,
+    /// Steps for compiling a benchmark, for compilers
+    #[serde(default)]
+    prepare: Vec<Step>,
+    /// Command that runs a benchmark
+    invoke: String,
+    /// Intermediate sources the prepare stage generates, such as C code
+    #[serde(default)]
+    generated: Vec<String>,
+    /// Commit from the lockfile to check out instead of following upstream
+    #[serde(skip)]
+    locked: Option<String>,
+}
+
+/// Loads every implementation in the manifest at the given path.
+pub fn load_manifest(path: &Path) -> Result<Vec<ManifestBfImpl>, String> {
+    let text = read_to_string(path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+    let manifest: Manifest =
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+
+    for i in &manifest.impls {
+        for step in i.build.iter().chain(i.prepare.iter()) {
+            if step.run.is_some() == step.cmake.is_some() {
+                return Err(format!(
+                    "{}: a step of {} must have exactly one of run and cmake",
+                    path.to_string_lossy(),
+                    i.name
+                ));
+            }
+        }
+        if i.source.git.is_some() && i.source.download.is_some() {
+            return Err(format!(
+                "{}: {} can't have both a git and a download source",
+                path.to_string_lossy(),
+                i.name
+            ));
+        }
+        i.source
+            .check_sha256()
+            .map_err(|e| format!("{}: source of {}: {}", path.to_string_lossy(), i.name, e))?;
+    }
+
+    Ok(manifest.impls)
+}
+
+/// If the current platform is in the list. An empty list means every platform.
+fn on_platform(platforms: &[String]) -> bool {
+    platforms.is_empty()
+        || platforms
+            .iter()
+            .any(|p| p == std::env::consts::OS || (p == "unix" && !windows()))
+}
+
+impl ManifestBfImpl {
+    /// Applies the source override for this implementation, if there is one. Fails if the merged
+    /// source is missing a sha256.
+    pub fn overridden(self, overrides: &SourceOverrides) -> Result<Self, String> {
+        let source = match overrides.source.get(&self.name) {
+            Some(over) => self.source.merge(over),
+            None => self.source.clone(),
+        };
+        source
+            .check_sha256()
+            .map_err(|e| format!("source of {}: {}", self.name, e))?;
+        let src = match &source.path {
+            Some(path) if source.in_place => path.clone(),
+            _ => self.src.clone(),
+        };
+        Ok(ManifestBfImpl {
+            source,
+            src,
+            ..self
+        })
+    }
+
+    /// Pins the implementation to its revision in the lockfile, if it has one.
+    pub fn locked(self, lockfile: &Lockfile) -> Self {
+        ManifestBfImpl {
+            locked: lockfile.revisions.get(&self.name).cloned(),
+            ..self
+        }
+    }
+
+    /// Fills in the placeholders of a manifest string.
+    fn expand(&self, template: &str, file: Option<&Path>) -> String {
+        // `exe` goes first as it may use the other placeholders itself
+        let mut s = template
+            .replace("{exe}", &self.exe)
+            .replace("{root}", &current_dir().unwrap().to_string_lossy())
+            .replace("{src}", &self.src)
+            .replace("{out}", &self.out);
+        if let Some(file) = file {
+            s = s
+                .replace("{file}", &file.to_string_lossy())
+                .replace("{stem}", &file.file_stem().unwrap().to_string_lossy());
+        }
+        s
+    }
+
+    /// Checks a downloaded or local file against the SHA-256 in the source, if there is one.
+    fn verify_artifact(&self, file: &str) -> StageResult {
+        match &self.source.sha256 {
+            Some(expected) => verify_sha256(file, expected),
+            None => {
+                println!(
+                    "Warning: {} has no sha256, {} was not verified",
+                    self.name, file
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn run_steps(&self, steps: &[Step], file: Option<&Path>) -> StageResult {
+        create_dir_all(&self.out).map_err(io_error)?;
+
+        for step in steps.iter().filter(|s| on_platform(&s.platforms)) {
+            let dir = self.expand(step.dir.as_ref().unwrap_or(&self.src), file);
+            if let Some(run) = &step.run {
+                let mut command = shell_command(&self.expand(run, file));
+                command.current_dir(&dir);
+                match &step.stdout {
+                    Some(stdout) => {
+                        run_command_with_pipe(&mut command, &self.expand(stdout, file))?
+                    }
+                    None => run_command(&mut command)?,
+                }
+            }
+            if let Some(name) = &step.cmake {
+                if let Some(sources) = &step.sources {
+                    create_cmake(name, &dir, &self.expand(sources, file))?;
+                }
+                build_cmake(name, &self.out, &dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BFImpl for ManifestBfImpl {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn interpreted(&self) -> bool {
+        self.interpreted
+    }
+
+    fn enabled(&self) -> bool {
+        on_platform(&self.platforms)
+    }
+
+    fn get(&self) -> StageResult {
+        if let Some(path) = &self.source.path {
+            if self.source.in_place {
+                Ok(())
+            } else if Path::new(path).is_dir() {
+                copy_dir(Path::new(path), Path::new(&self.src))
+            } else {
+                self.verify_artifact(path)?;
+                if let Some(parent) = Path::new(&self.expand("{exe}", None)).parent() {
+                    create_dir_all(parent).map_err(io_error)?;
+                }
+                std::fs::copy(path, self.expand("{exe}", None))
+                    .map(|_| ())
+                    .map_err(|e| format!("{}: {}", path, e))
+            }
+        } else if let Some(tarball) = &self.source.tarball {
+            self.verify_artifact(tarball)?;
+            extract_tarball(tarball, &self.src, self.source.strip)
+        } else if let Some(url) = self.source.git_url() {
+            match (&self.locked, &self.source.branch) {
+                (Some(rev), _) => git_checkout(url, &self.src, rev),
+                (None, Some(branch)) => git_repo_branch(url, &self.src, branch),
+                (None, None) => {
+                    println!(
+                        "{} isn't in the lockfile, so it follows upstream. Run `update` to pin it.",
+                        self.name
+                    );
+                    git_repo(url, &self.src)
+                }
+            }
+        } else if let Some(url) = &self.source.download {
+            if let Some(parent) = Path::new(&self.expand("{exe}", None)).parent() {
+                create_dir_all(parent).map_err(io_error)?;
+            }
+            let exe = self.expand("{exe}", None);
+            curl_file(url, &exe)?;
+            self.verify_artifact(&exe).map_err(|e| {
+                // Don't leave a binary we don't trust lying around to be run
+                let _ = std::fs::remove_file(&exe);
+                format!("{}\nThe download was removed.", e)
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update(&self) -> Result<Option<String>, String> {
+        match self.source.git_url() {
+            Some(url) => git_update(
+                url,
+                &self.src,
+                self.source.branch.as_ref().map(|b| b.as_str()),
+            )
+            .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn revision(&self) -> Option<String> {
+        let sha256 = |file: &str| sha256_file(file).ok().map(|h| format!("sha256:{}", h));
+        if let Some(file) = self.source.artifact() {
+            sha256(file)
+        } else if self.source.git_url().is_some() || self.source.path.is_some() {
+            // A folder that isn't a repo itself would get the revision of the repo around it
+            if !git_toplevel(&self.src) {
+                return None;
+            }
+            git_revision(&self.src).map(|rev| {
+                if git_dirty(&self.src) {
+                    format!("{}-dirty", rev)
+                } else {
+                    rev
+                }
+            })
+        } else if self.source.download.is_some() {
+            sha256(&self.expand("{exe}", None))
+        } else {
+            None
+        }
+    }
+
+    fn requirements(&self) -> Vec<Requirement> {
+        let steps: Vec<&Step> = self
+            .build
+            .iter()
+            .chain(self.prepare.iter())
+            .filter(|s| on_platform(&s.platforms))
+            .collect();
+
+        let mut requirements = self.requires.clone();
+        requirements.extend(steps.iter().flat_map(|s| s.requires.iter().cloned()));
+        if self.source.path.is_none() {
+            if self.source.tarball.is_some() {
+                requirements.push(Requirement::tool("tar"));
+            } else if self.source.git_url().is_some() {
+                requirements.push(Requirement::tool("git"));
+            } else if self.source.download.is_some() {
+                requirements.push(Requirement::tool("curl"));
+            }
+        }
+        if steps.iter().any(|s| s.cmake.is_some()) {
+            requirements.push("cmake >= 3.12".parse().unwrap());
+        }
+
+        // The first mention of a tool wins, so explicit versions beat the implied ones
+        let mut seen = Vec::new();
+        requirements.retain(|r| {
+            let new = !seen.contains(&r.tool);
+            seen.push(r.tool.clone());
+            new
+        });
+        requirements
+    }
+
+    fn build_recipe(&self) -> String {
+        let steps: Vec<&Step> = self
+            .build
+            .iter()
+            .filter(|s| on_platform(&s.platforms))
+            .collect();
+        format!(
+            "{:?} {:?} {:?} {:?} {:?}",
+            self.src, self.out, self.exe, self.source, steps
+        )
+    }
+
+    fn build(&self) -> StageResult {
+        self.run_steps(&self.build, None)
+    }
+
+    fn prepare(&self, file: PathBuf) -> StageResult {
+        if self.prepare.is_empty() {
+            return Ok(());
+        }
+        self.run_steps(&self.prepare, Some(&file))
+    }
+
+    fn compiled_exe(&self) -> Option<PathBuf> {
+        if self.interpreted {
+            return None;
+        }
+        let exe = PathBuf::from(self.expand("{exe}", None));
+        if windows() && exe.extension().is_none() {
+            Some(exe.with_extension("exe"))
+        } else {
+            Some(exe)
+        }
+    }
+
+    fn generated_sources(&self) -> Vec<PathBuf> {
+        self.generated
+            .iter()
+            .map(|g| PathBuf::from(self.expand(g, None)))
+            .collect()
+    }
+
+    fn cached_exe(&self, file: &Path) -> Option<PathBuf> {
+        let exe = self.compiled_exe()?;
+        let revision = self.revision().filter(|r| !r.ends_with("-dirty"))?;
+        let bench = std::fs::read(file).ok()?;
+        let prepare: Vec<&Step> = self
+            .prepare
+            .iter()
+            .filter(|s| on_platform(&s.platforms))
+            .collect();
+        let compilers = compiler_versions(&self.requirements());
+        let key = artifact_key(&[
+            revision.as_bytes(),
+            self.build_recipe().as_bytes(),
+            format!("{:?} {:?} {:?}", prepare, self.invoke, compilers).as_bytes(),
+            &bench,
+        ]);
+        let stem = file.file_stem()?.to_string_lossy();
+        Some(
+            path_dsl::path!(
+                CACHE_DIR
+                    | (slug(&self.name))
+                    | (format!("{}-{}", stem, key))
+                    | (exe.file_name()?)
+            )
+            .into(),
+        )
+    }
+
+    fn get_invoke_command(&self, file: PathBuf) -> String {
+        match self.cached_exe(&file) {
+            Some(exe) => self.expand(
+                &self.invoke.replace("{exe}", &exe.to_string_lossy()),
+                Some(&file),
+            ),
+            None => self.expand(&self.invoke, Some(&file)),
+        }
+    }
+
+    fn website(&self) -> String {
+        self.website.clone()
+    }
+}
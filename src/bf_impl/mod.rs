@@ -1,23 +1,5 @@
-pub use apankrat_bff::*;
-pub use asumagic_ashbf::*;
-pub use cwfitzgerald_bfcc::*;
-pub use cwfitzgerald_bfcc_old::*;
-pub use dethraid_brainfuck::*;
-pub use gardrek_stvm::*;
-pub use kotay_bffsree::*;
-pub use lifthrasiir_esotope_bfc::*;
-pub use rdebath_tritium::*;
-pub use rinoldm_sbfi::*;
-pub use wilfred_bfc::*;
+pub use builtin_reference::*;
+pub use manifest::*;
 
-mod apankrat_bff;
-mod asumagic_ashbf;
-mod cwfitzgerald_bfcc;
-mod cwfitzgerald_bfcc_old;
-mod dethraid_brainfuck;
-mod gardrek_stvm;
-mod kotay_bffsree;
-mod lifthrasiir_esotope_bfc;
-mod rdebath_tritium;
-mod rinoldm_sbfi;
-mod wilfred_bfc;
+mod builtin_reference;
+mod manifest;
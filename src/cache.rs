@@ -0,0 +1,56 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Folder holding a compiled executable per implementation and benchmark
+pub const CACHE_DIR: &str = "build/cache";
+
+/// What is known about a cached executable besides the executable itself
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CachedArtifact {
+    /// Time it took to compile it, from when it was compiled
+    pub compile: Option<CompileTiming>,
+    pub size: Option<ExeSize>,
+}
+
+fn metadata_path(exe: &Path) -> PathBuf {
+    exe.with_file_name("artifact.json")
+}
+
+/// Short hash identifying everything that went into a compiled executable
+pub fn artifact_key(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Hash the length too, so parts can't run into each other
+        hasher.input(&(part.len() as u64).to_le_bytes());
+        hasher.input(part);
+    }
+    format!("{:x}", hasher.result())[..16].to_string()
+}
+
+/// Loads a cached executable, if it exists.
+pub fn load_artifact(exe: &Path) -> Option<CachedArtifact> {
+    if !exe.exists() {
+        return None;
+    }
+    let text = read_to_string(metadata_path(exe)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Copies the executable the implementation just compiled into the cache.
+pub fn store_artifact(b: &dyn BFImpl, exe: &Path, artifact: &CachedArtifact) -> StageResult {
+    let compiled = b
+        .compiled_exe()
+        .ok_or_else(|| format!("{} didn't compile an executable", b.name()))?;
+    create_dir_all(exe.parent().unwrap()).map_err(io_error)?;
+    std::fs::copy(&compiled, exe).map_err(|e| {
+        format!(
+            "Failed to cache {} as {}: {}",
+            compiled.to_string_lossy(),
+            exe.to_string_lossy(),
+            e
+        )
+    })?;
+    let text = serde_json::to_string_pretty(artifact).map_err(|e| e.to_string())?;
+    std::fs::write(metadata_path(exe), text).map_err(io_error)
+}
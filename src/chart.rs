@@ -0,0 +1,154 @@
+use crate::*;
+
+/// Width of the longest bar in characters
+const BAR_WIDTH: usize = 40;
+
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const MAGENTA: &str = "35";
+const CYAN: &str = "36";
+
+/// If stdout is a terminal which should get colors
+fn use_color() -> bool {
+    if std::env::var_os("NO_COLOR").is_some() {
+        return false;
+    }
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            unsafe { libc::isatty(1) == 1 }
+        } else {
+            false
+        }
+    }
+}
+
+fn paint(text: &str, color: &str) -> String {
+    if use_color() {
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Horizontal bar of `fraction` of the full width, in eighths of a character
+fn bar(fraction: f64) -> String {
+    const PARTIAL: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let eighths = (fraction.max(0.0).min(1.0) * (BAR_WIDTH * 8) as f64).round() as usize;
+    let eighths = eighths.max(1);
+    let text = format!("{}{}", "█".repeat(eighths / 8), PARTIAL[eighths % 8]);
+    let padding = BAR_WIDTH - (eighths + 7) / 8;
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+fn status_color(status: Status) -> &'static str {
+    match status {
+        Status::Ok => GREEN,
+        Status::Failed => RED,
+        Status::WrongOutput => MAGENTA,
+        Status::Timeout => YELLOW,
+    }
+}
+
+/// Prints a bar per implementation scaled to its mean time, followed by the ones that failed.
+pub fn print_benchmark_chart(bench: &BenchmarkResults) {
+    let mut timed: Vec<_> = bench.timed().collect();
+    timed.sort_by(|a, b| a.1.wall.mean.partial_cmp(&b.1.wall.mean).unwrap());
+
+    let width = bench
+        .implementations
+        .iter()
+        .map(|r| r.implementation.len())
+        .max()
+        .unwrap_or(0);
+
+    println!("\n{}", bench.benchmark);
+    let fastest = timed.first().map(|(_, m)| m.wall.mean);
+    let slowest = timed.last().map_or(0.0, |(_, m)| m.wall.mean);
+    for (r, m) in &timed {
+        let relative = m.wall.mean / fastest.unwrap();
+        println!(
+            "  {:<width$}  {}  {:>10} ± {:<10} {:>7}",
+            r.implementation,
+            paint(
+                &bar(m.wall.mean / slowest),
+                if relative == 1.0 { GREEN } else { CYAN }
+            ),
+            format_time(m.wall.mean),
+            format_time(m.wall.stddev),
+            format!("{:.2}×", relative),
+            width = width
+        );
+    }
+    for r in bench
+        .implementations
+        .iter()
+        .filter(|r| r.status != Status::Ok)
+    {
+        println!(
+            "  {:<width$}  {}  {}",
+            r.implementation,
+            paint(&format!("{:<12}", r.status.label()), status_color(r.status)),
+            r.reason.as_ref().map_or("", |s| s.as_str()),
+            width = width
+        );
+    }
+}
+
+/// Prints a bar per implementation scaled to the geometric mean of its slowdowns over all
+/// benchmarks, along with how often it failed or timed out.
+pub fn print_overall_chart(results: &Results) {
+    let rows = results.summary();
+    if rows.is_empty() {
+        return;
+    }
+
+    let width = rows
+        .iter()
+        .map(|r| r.implementation.len())
+        .max()
+        .unwrap_or(0);
+    let slowest = rows
+        .iter()
+        .filter_map(|r| r.geometric_mean)
+        .fold(1.0, f64::max);
+
+    println!("\nOverall, by geometric mean of the slowdown against the fastest on each benchmark");
+    for row in &rows {
+        let chart = match row.geometric_mean {
+            Some(g) => format!(
+                "{}  {:>7}",
+                paint(
+                    &bar(g / slowest),
+                    if row.rank == Some(1) { GREEN } else { CYAN }
+                ),
+                format!("{:.2}×", g)
+            ),
+            // As wide as a bar and its factor
+            None => format!("{:<width$}", "-", width = BAR_WIDTH + 9),
+        };
+        let wrong = row
+            .statuses
+            .iter()
+            .filter(|s| **s == Some(Status::WrongOutput))
+            .count();
+        let mut markers = Vec::new();
+        if row.failures > wrong {
+            markers.push(paint(&format!("{} failed", row.failures - wrong), RED));
+        }
+        if wrong > 0 {
+            markers.push(paint(&format!("{} wrong output", wrong), MAGENTA));
+        }
+        if row.timeouts > 0 {
+            markers.push(paint(&format!("{} timed out", row.timeouts), YELLOW));
+        }
+        let line = format!(
+            "  {:<width$}  {}  {}",
+            row.implementation,
+            chart,
+            markers.join(", "),
+            width = width
+        );
+        println!("{}", line.trim_end());
+    }
+}
@@ -0,0 +1,636 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Folder every run is archived in, one file per run named after its id
+pub const HISTORY_DIR: &str = "history";
+
+/// What the benchmarks ran on and how
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Environment {
+    /// When the run started, in UTC
+    pub started: String,
+    pub host: Option<String>,
+    pub os: String,
+    pub arch: String,
+    /// Kernel name and release, on unix
+    pub kernel: Option<String>,
+    /// CPU model, on linux
+    pub cpu: Option<String>,
+    /// Logical CPUs, on linux
+    pub cpus: Option<usize>,
+    /// Revision of this benchmark suite
+    pub suite_revision: Option<String>,
+    pub backend: String,
+    pub warmup: usize,
+    pub runs: usize,
+    /// Target coefficient of variation of the mean in percent, if sampling was adaptive
+    #[serde(default)]
+    pub target_cv: Option<f64>,
+}
+
+impl Environment {
+    pub fn capture(opt: &Options) -> Self {
+        let cpuinfo = read_to_string("/proc/cpuinfo").ok();
+        Environment {
+            started: timestamp(SystemTime::now()),
+            host: command_output("hostname", &[]),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            kernel: command_output("uname", &["-sr"]),
+            cpu: cpuinfo.as_ref().and_then(|info| {
+                info.lines()
+                    .find(|l| l.starts_with("model name"))
+                    .and_then(|l| l.splitn(2, ':').nth(1))
+                    .map(|m| m.trim().to_string())
+            }),
+            cpus: cpuinfo
+                .as_ref()
+                .map(|info| info.lines().filter(|l| l.starts_with("processor")).count())
+                .filter(|&n| n > 0),
+            suite_revision: git_revision(".").map(|rev| {
+                if git_dirty(".") {
+                    format!("{}-dirty", rev)
+                } else {
+                    rev
+                }
+            }),
+            backend: format!("{:?}", opt.backend).to_lowercase(),
+            warmup: opt.warmup,
+            runs: opt.runs,
+            target_cv: opt.target_cv,
+        }
+    }
+
+    /// Every field as a name and value, for showing how two environments differ
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let or_unknown = |v: &Option<String>| v.clone().unwrap_or_else(|| String::from("?"));
+        vec![
+            ("host", or_unknown(&self.host)),
+            ("os", format!("{} {}", self.os, self.arch)),
+            ("kernel", or_unknown(&self.kernel)),
+            ("cpu", or_unknown(&self.cpu)),
+            ("cpus", or_unknown(&self.cpus.map(|n| n.to_string()))),
+            ("suite revision", or_unknown(&self.suite_revision)),
+            ("backend", self.backend.clone()),
+            ("warmup", self.warmup.to_string()),
+            ("runs", self.runs.to_string()),
+            (
+                "target cv",
+                self.target_cv
+                    .map_or_else(|| String::from("off"), |cv| format!("{}%", cv)),
+            ),
+        ]
+    }
+}
+
+/// A run as kept in the history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedRun {
+    pub id: String,
+    pub environment: Environment,
+    /// Results of the implementations that ran, before merging with earlier runs
+    pub results: Results,
+}
+
+impl ArchivedRun {
+    /// Saves the results of a run under a new id made from its start time.
+    pub fn archive(environment: Environment, results: Results) -> Result<Self, String> {
+        create_dir_all(HISTORY_DIR).map_err(io_error)?;
+        let base = environment.started.replace(':', "-");
+        let mut id = base.clone();
+        let mut n = 1;
+        while run_path(&id).exists() {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+
+        let run = ArchivedRun {
+            id,
+            environment,
+            results,
+        };
+        let file = File::create(run_path(&run.id)).map_err(io_error)?;
+        serde_json::to_writer_pretty(file, &run).map_err(|e| e.to_string())?;
+        Ok(run)
+    }
+
+    /// Loads a run by id, unique id prefix, `latest`, `previous` or path to an archived run.
+    pub fn load(spec: &str) -> Result<Self, String> {
+        let path = if Path::new(spec).is_file() {
+            PathBuf::from(spec)
+        } else {
+            run_path(&find_run(spec)?)
+        };
+        let file = File::open(&path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+        serde_json::from_reader(file).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+    }
+}
+
+fn run_path(id: &str) -> PathBuf {
+    Path::new(HISTORY_DIR).join(format!("{}.json", id))
+}
+
+/// Ids of every archived run, oldest first
+pub fn archived_runs() -> Vec<String> {
+    let mut ids: Vec<String> = read_dir(HISTORY_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map_or(false, |e| e == "json"))
+                .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+fn find_run(spec: &str) -> Result<String, String> {
+    let ids = archived_runs();
+    let found = match spec {
+        "latest" => ids.last().cloned(),
+        "previous" => ids.iter().rev().nth(1).cloned(),
+        _ => {
+            let matching: Vec<&String> = ids.iter().filter(|id| id.starts_with(spec)).collect();
+            match matching.as_slice() {
+                [id] => Some((*id).clone()),
+                [] => None,
+                _ => {
+                    return Err(format!(
+                        "{} matches several runs: {}",
+                        spec,
+                        matching.iter().join(", ")
+                    ))
+                }
+            }
+        }
+    };
+    found.ok_or_else(|| {
+        if ids.is_empty() {
+            format!("No run {}, {} is empty", spec, HISTORY_DIR)
+        } else {
+            format!(
+                "No run {}, the archived runs are:\n  {}",
+                spec,
+                ids.join("\n  ")
+            )
+        }
+    })
+}
+
+/// Formats the time as `YYYY-MM-DDTHH:MM:SSZ`
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0) as i64;
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // Civil date from days since the epoch, after Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Trimmed stdout of a command, None if it couldn't be run
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Mean time in a readable unit
+pub fn format_time(seconds: f64) -> String {
+    if seconds < 1.0 {
+        format!("{:.2} ms", seconds * 1000.0)
+    } else {
+        format!("{:.3} s", seconds)
+    }
+}
+
+/// Mean time of the result, its status if it wasn't timed or `-` if there is none
+fn describe_result(r: Option<&ImplementationResult>) -> String {
+    match r {
+        Some(r) => match &r.measurement {
+            Some(m) => format_time(m.wall.mean),
+            None => r.status.label().to_string(),
+        },
+        None => String::from("-"),
+    }
+}
+
+/// Prints how every implementation changed between two runs, per benchmark.
+pub fn compare_runs(
+    a: &ArchivedRun,
+    b: &ArchivedRun,
+    implementation_selected: impl Fn(&ImplementationResult) -> bool,
+    benchmark_selected: impl Fn(&str) -> bool,
+) {
+    println!("A: {} ({})", a.id, a.environment.started);
+    println!("B: {} ({})", b.id, b.environment.started);
+
+    let differences: Vec<String> = a
+        .environment
+        .fields()
+        .into_iter()
+        .zip(b.environment.fields())
+        .filter(|((_, va), (_, vb))| va != vb)
+        .map(|((name, va), (_, vb))| format!("  {}: {} -> {}", name, va, vb))
+        .collect();
+    if !differences.is_empty() {
+        println!("Environment differences:\n{}", differences.join("\n"));
+    }
+
+    let benchmarks: BTreeSet<&str> = a
+        .results
+        .benchmarks
+        .iter()
+        .chain(&b.results.benchmarks)
+        .map(|bench| bench.benchmark.as_str())
+        .filter(|name| benchmark_selected(name))
+        .collect();
+
+    for benchmark in benchmarks {
+        let find = |run: &'_ ArchivedRun| {
+            run.results
+                .benchmarks
+                .iter()
+                .find(|bench| bench.benchmark == benchmark)
+                .map(|bench| bench.implementations.clone())
+                .unwrap_or_default()
+        };
+        let (in_a, in_b) = (find(a), find(b));
+        let names: BTreeSet<&str> = in_a
+            .iter()
+            .chain(&in_b)
+            .filter(|r| implementation_selected(r))
+            .map(|r| r.implementation.as_str())
+            .collect();
+        if names.is_empty() {
+            continue;
+        }
+
+        let width = names
+            .iter()
+            .map(|n| n.len())
+            .chain(Some("Implementation".len()))
+            .max()
+            .unwrap();
+        println!("\n{}", benchmark);
+        println!(
+            "  {:<width$}  {:>12}  {:>12}  {:>8}",
+            "Implementation",
+            "A",
+            "B",
+            "Change",
+            width = width
+        );
+        for name in names {
+            let ra = in_a.iter().find(|r| r.implementation == name);
+            let rb = in_b.iter().find(|r| r.implementation == name);
+            let measurements = (
+                ra.and_then(|r| r.measurement.as_ref()),
+                rb.and_then(|r| r.measurement.as_ref()),
+            );
+            let (change, marker) = match measurements {
+                (Some(ma), Some(mb)) => (
+                    format!("{:+.1}%", (mb.wall.mean / ma.wall.mean - 1.0) * 100.0),
+                    significance_marker(welch_t_test(&ma.times, &mb.times)),
+                ),
+                _ => (String::new(), ""),
+            };
+            let line = format!(
+                "  {:<width$}  {:>12}  {:>12}  {:>8} {}",
+                name,
+                describe_result(ra),
+                describe_result(rb),
+                change,
+                marker,
+                width = width
+            );
+            println!("{}", line.trim_end());
+        }
+    }
+
+    println!("\nChange is B relative to A. Significance of Welch's t-test: * p < 0.05, ** p < 0.01, *** p < 0.001");
+}
+
+/// How much slower than the baseline a run may get
+#[derive(Copy, Clone, Debug)]
+pub struct GateThresholds {
+    /// Largest allowed slowdown in percent
+    pub max_slowdown: f64,
+    /// p-value of Welch's t-test below which any slowdown counts
+    pub significance: f64,
+}
+
+/// Checks every pair in the run against the baseline and prints why each passed or failed.
+/// Returns if none of them regressed.
+pub fn regression_gate(
+    baseline: &ArchivedRun,
+    current: &Results,
+    thresholds: GateThresholds,
+) -> bool {
+    let mut rows = Vec::new();
+    let mut passed = true;
+
+    for bench in &current.benchmarks {
+        let old_bench = baseline
+            .results
+            .benchmarks
+            .iter()
+            .find(|b| b.benchmark == bench.benchmark);
+        for new in &bench.implementations {
+            let old = old_bench.and_then(|b| {
+                b.implementations
+                    .iter()
+                    .find(|r| r.implementation == new.implementation)
+            });
+            let mut change = String::new();
+            let mut p_value = String::new();
+            let verdict = match (old.and_then(|r| r.measurement.as_ref()), &new.measurement) {
+                (None, _) if old.is_none() => String::from("new, not checked"),
+                (None, Some(_)) => String::from("ok, fixed"),
+                (None, None) => String::from("ok, failed before too"),
+                (Some(_), None) => {
+                    passed = false;
+                    format!("REGRESSED, {}", new.status.label())
+                }
+                (Some(mo), Some(mn)) => {
+                    let slowdown = (mn.wall.mean / mo.wall.mean - 1.0) * 100.0;
+                    let p = welch_t_test(&mo.times, &mn.times);
+                    change = format!("{:+.1}%", slowdown);
+                    p_value = p.map_or_else(|| String::from("-"), |p| format!("{:.4}", p));
+                    if slowdown > thresholds.max_slowdown {
+                        passed = false;
+                        format!("REGRESSED, over {}%", thresholds.max_slowdown)
+                    } else if slowdown > 0.0 && p.map_or(false, |p| p < thresholds.significance) {
+                        passed = false;
+                        format!("REGRESSED, p < {}", thresholds.significance)
+                    } else {
+                        String::from("ok")
+                    }
+                }
+            };
+            rows.push(vec![
+                bench.benchmark.clone(),
+                new.implementation.clone(),
+                describe_result(old),
+                describe_result(Some(new)),
+                change,
+                p_value,
+                verdict,
+            ]);
+        }
+    }
+
+    // Implementations that didn't get to run at all regressed everywhere they used to run
+    for f in current.unrun_stage_failures() {
+        let ran_before = baseline.results.benchmarks.iter().any(|b| {
+            b.implementations
+                .iter()
+                .any(|r| r.implementation == f.implementation && r.measurement.is_some())
+        });
+        if ran_before {
+            passed = false;
+            rows.push(vec![
+                f.benchmark.clone().unwrap_or_else(|| String::from("-")),
+                f.implementation.clone(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                format!("REGRESSED, {} failed", f.describe()),
+            ]);
+        }
+    }
+
+    let header = [
+        "Benchmark",
+        "Implementation",
+        "Baseline",
+        "Current",
+        "Change",
+        "p",
+        "Verdict",
+    ];
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(Some(header[i].len()))
+                .max()
+                .unwrap()
+        })
+        .collect();
+    let format_row = |row: Vec<&str>| {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &width))| match i {
+                2..=5 => format!("{:>width$}", cell, width = width),
+                _ => format!("{:<width$}", cell, width = width),
+            })
+            .join("  ");
+        format!("  {}", cells.trim_end())
+    };
+
+    println!("==========================================");
+    println!(
+        "Regression check against {} ({}): fails if over {}% slower, or slower at p < {}",
+        baseline.id, baseline.environment.started, thresholds.max_slowdown, thresholds.significance
+    );
+    println!("{}", format_row(header.to_vec()));
+    for row in &rows {
+        println!("{}", format_row(row.iter().map(|c| c.as_str()).collect()));
+    }
+    println!(
+        "{}",
+        if passed {
+            "No regressions"
+        } else {
+            "Regressions found"
+        }
+    );
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: GateThresholds = GateThresholds {
+        max_slowdown: 5.0,
+        significance: 0.01,
+    };
+
+    fn timed(implementation: &str, times: &[f64]) -> ImplementationResult {
+        ImplementationResult {
+            implementation: implementation.to_string(),
+            website: String::new(),
+            interpreted: true,
+            status: Status::Ok,
+            reason: None,
+            measurement: Some(Measurement {
+                command: String::new(),
+                times: times.to_vec(),
+                wall: Statistics::from_values(times),
+                user: 0.0,
+                system: 0.0,
+                max_rss: Vec::new(),
+                stopped: None,
+            }),
+            compile: None,
+            size: None,
+        }
+    }
+
+    fn failed(implementation: &str) -> ImplementationResult {
+        ImplementationResult {
+            status: Status::Failed,
+            reason: Some(String::from("exited with 1")),
+            measurement: None,
+            ..timed(implementation, &[1.0])
+        }
+    }
+
+    fn stage_failure(implementation: &str, stage: &str) -> StageFailure {
+        StageFailure {
+            implementation: implementation.to_string(),
+            website: String::new(),
+            stage: stage.to_string(),
+            benchmark: None,
+            log: String::new(),
+        }
+    }
+
+    fn results(implementations: Vec<ImplementationResult>) -> Results {
+        Results {
+            benchmarks: vec![BenchmarkResults {
+                benchmark: String::from("bench.b"),
+                implementations,
+            }],
+            ..Results::default()
+        }
+    }
+
+    fn baseline(implementations: Vec<ImplementationResult>) -> ArchivedRun {
+        ArchivedRun {
+            id: String::from("baseline"),
+            environment: Environment {
+                started: String::from("2019-10-04T00:00:00Z"),
+                host: None,
+                os: String::from("linux"),
+                arch: String::from("x86_64"),
+                kernel: None,
+                cpu: None,
+                cpus: None,
+                suite_revision: None,
+                backend: String::from("native"),
+                warmup: 0,
+                runs: 5,
+                target_cv: None,
+            },
+            results: results(implementations),
+        }
+    }
+
+    const BASE: [f64; 5] = [1.0, 1.001, 0.999, 1.0005, 0.9995];
+
+    #[test]
+    fn unchanged_passes() {
+        let base = baseline(vec![timed("a", &BASE)]);
+        assert!(regression_gate(
+            &base,
+            &results(vec![timed("a", &BASE)]),
+            THRESHOLDS
+        ));
+    }
+
+    #[test]
+    fn over_threshold_fails() {
+        let slower: Vec<f64> = BASE.iter().map(|t| t * 1.2).collect();
+        let base = baseline(vec![timed("a", &BASE)]);
+        assert!(!regression_gate(
+            &base,
+            &results(vec![timed("a", &slower)]),
+            THRESHOLDS
+        ));
+    }
+
+    #[test]
+    fn significant_small_slowdown_fails() {
+        let slower: Vec<f64> = BASE.iter().map(|t| t * 1.02).collect();
+        let base = baseline(vec![timed("a", &BASE)]);
+        assert!(!regression_gate(
+            &base,
+            &results(vec![timed("a", &slower)]),
+            THRESHOLDS
+        ));
+    }
+
+    #[test]
+    fn noisy_small_slowdown_passes() {
+        let base = baseline(vec![timed("a", &[0.9, 1.1, 1.0])]);
+        assert!(regression_gate(
+            &base,
+            &results(vec![timed("a", &[0.93, 1.13, 1.03])]),
+            THRESHOLDS
+        ));
+    }
+
+    #[test]
+    fn newly_failing_fails() {
+        let base = baseline(vec![timed("a", &BASE)]);
+        assert!(!regression_gate(
+            &base,
+            &results(vec![failed("a")]),
+            THRESHOLDS
+        ));
+    }
+
+    #[test]
+    fn new_and_fixed_pairs_pass() {
+        let base = baseline(vec![failed("a")]);
+        let current = results(vec![timed("a", &BASE), failed("b")]);
+        assert!(regression_gate(&base, &current, THRESHOLDS));
+    }
+
+    #[test]
+    fn stage_failure_fails_only_if_it_ran_before() {
+        let base = baseline(vec![timed("a", &BASE), failed("b")]);
+        let mut current = results(Vec::new());
+        current.stage_failures = vec![stage_failure("b", "build")];
+        assert!(regression_gate(&base, &current, THRESHOLDS));
+
+        current.stage_failures.push(stage_failure("a", "fetch"));
+        assert!(!regression_gate(&base, &current, THRESHOLDS));
+    }
+}
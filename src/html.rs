@@ -0,0 +1,319 @@
+use crate::*;
+
+/// Height of one bar or heatmap row in pixels
+const ROW_HEIGHT: f64 = 22.0;
+/// Rough width of a character of the 12px labels in pixels
+const CHAR_WIDTH: f64 = 7.0;
+/// Width of the bars of a bar chart at their longest
+const BAR_WIDTH: f64 = 480.0;
+/// Width of a heatmap cell
+const CELL_WIDTH: f64 = 72.0;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 1200px; color: #222; }
+h1, h2 { font-weight: normal; }
+svg { display: block; margin: 1em 0; font-size: 12px; }
+svg a text, a { fill: #0645ad; color: #0645ad; }
+.failed { color: #a00; }
+table { border-collapse: collapse; }
+td, th { padding: 2px 8px; text-align: left; }
+";
+
+/// Escapes text for use in HTML and SVG, including attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Label linking to the implementation's website
+fn svg_link(x: f64, y: f64, anchor: &str, name: &str, website: &str) -> String {
+    format!(
+        "<a href=\"{}\"><text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" dominant-baseline=\"middle\">{}</text></a>",
+        escape(website),
+        x,
+        y,
+        anchor,
+        escape(name)
+    )
+}
+
+/// Color of a heatmap cell, from green for the fastest to red for the slowest
+fn heat_color(slowdown: f64, slowest: f64) -> String {
+    let position = if slowest > 1.0 {
+        (slowdown.ln() / slowest.ln()).min(1.0).max(0.0)
+    } else {
+        0.0
+    };
+    format!("hsl({:.0}, 65%, 55%)", 120.0 * (1.0 - position))
+}
+
+/// Overview of every implementation on every benchmark, colored by slowdown on a log scale
+fn render_heatmap(results: &Results) -> String {
+    let rows = results.summary();
+    let slowest = rows
+        .iter()
+        .flat_map(|r| r.slowdowns.iter().filter_map(|s| *s))
+        .fold(1.0, f64::max);
+    let label_width = rows
+        .iter()
+        .map(|r| r.implementation.len())
+        .max()
+        .unwrap_or(0) as f64
+        * CHAR_WIDTH
+        + 10.0;
+    let top = 30.0;
+    let width = label_width + CELL_WIDTH * (results.benchmarks.len() + 1) as f64;
+    let height = top + ROW_HEIGHT * rows.len() as f64 + 40.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">\n",
+        width, height
+    );
+    for (i, bench) in results.benchmarks.iter().enumerate() {
+        let stem = Path::new(&bench.benchmark)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        svg += &format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+            label_width + CELL_WIDTH * (i as f64 + 0.5),
+            top - 10.0,
+            escape(&stem)
+        );
+    }
+    svg += &format!(
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">geo. mean</text>\n",
+        label_width + CELL_WIDTH * (results.benchmarks.len() as f64 + 0.5),
+        top - 10.0
+    );
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = top + ROW_HEIGHT * row_index as f64;
+        svg += &svg_link(
+            label_width - 10.0,
+            y + ROW_HEIGHT / 2.0,
+            "end",
+            &row.implementation,
+            &row.website,
+        );
+        svg += "\n";
+
+        let cells = row
+            .slowdowns
+            .iter()
+            .zip(&row.statuses)
+            .map(|(slowdown, status)| (*slowdown, *status))
+            .chain(Some((row.geometric_mean, None)));
+        for (i, (slowdown, status)) in cells.enumerate() {
+            let x = label_width + CELL_WIDTH * i as f64;
+            let (fill, text) = match (slowdown, status) {
+                (Some(s), _) => (heat_color(s, slowest), format!("{:.2}×", s)),
+                (None, Some(status)) => (String::from("#ccc"), status.label().to_string()),
+                (None, None) => (String::from("#eee"), String::from("-")),
+            };
+            svg += &format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"#fff\"/><text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"middle\" font-size=\"10\">{}</text>\n",
+                x,
+                y,
+                CELL_WIDTH,
+                ROW_HEIGHT,
+                fill,
+                x + CELL_WIDTH / 2.0,
+                y + ROW_HEIGHT / 2.0,
+                escape(&text)
+            );
+        }
+    }
+
+    // Legend of the log scale
+    let legend_y = top + ROW_HEIGHT * rows.len() as f64 + 15.0;
+    let steps = 10;
+    for step in 0..steps {
+        let slowdown = slowest.powf(step as f64 / (steps - 1) as f64);
+        svg += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"20\" height=\"10\" fill=\"{}\"/>",
+            label_width + 20.0 * step as f64,
+            legend_y,
+            heat_color(slowdown, slowest)
+        );
+    }
+    svg += &format!(
+        "\n<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">1×</text><text x=\"{:.1}\" y=\"{:.1}\" dominant-baseline=\"middle\">{:.1}× (log scale)</text>\n",
+        label_width - 5.0,
+        legend_y + 5.0,
+        label_width + 20.0 * steps as f64 + 5.0,
+        legend_y + 5.0,
+        slowest
+    );
+    svg += "</svg>\n";
+    svg
+}
+
+/// Bar chart of the mean time of every timed implementation, with its confidence interval as
+/// error bars
+fn render_bar_chart(bench: &BenchmarkResults) -> String {
+    let mut timed: Vec<_> = bench.timed().collect();
+    if timed.is_empty() {
+        return String::new();
+    }
+    timed.sort_by(|a, b| a.1.wall.mean.partial_cmp(&b.1.wall.mean).unwrap());
+
+    let intervals: Vec<(f64, f64)> = timed
+        .iter()
+        .map(|(_, m)| bootstrap_mean_ci(&m.times, CONFIDENCE))
+        .collect();
+    let longest = intervals
+        .iter()
+        .map(|&(_, high)| high)
+        .chain(timed.iter().map(|(_, m)| m.wall.mean))
+        .fold(0.0, f64::max);
+    let scale = if longest > 0.0 {
+        BAR_WIDTH / longest
+    } else {
+        0.0
+    };
+    let label_width = timed
+        .iter()
+        .map(|(r, _)| r.implementation.len())
+        .max()
+        .unwrap() as f64
+        * CHAR_WIDTH
+        + 10.0;
+    let width = label_width + BAR_WIDTH + 160.0;
+    let height = ROW_HEIGHT * timed.len() as f64 + 10.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">\n",
+        width, height
+    );
+    for (i, ((r, m), (low, high))) in timed.iter().zip(&intervals).enumerate() {
+        let y = ROW_HEIGHT * i as f64;
+        let middle = y + ROW_HEIGHT / 2.0;
+        svg += &svg_link(
+            label_width - 10.0,
+            middle,
+            "end",
+            &r.implementation,
+            &r.website,
+        );
+        svg += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}</title></rect>",
+            label_width,
+            y + 3.0,
+            m.wall.mean * scale,
+            ROW_HEIGHT - 6.0,
+            if r.interpreted { "#4a7ab5" } else { "#b5734a" },
+            escape(&format!(
+                "{}: mean {}, {:.0}% CI {} – {}, {} runs",
+                r.implementation,
+                format_time(m.wall.mean),
+                CONFIDENCE * 100.0,
+                format_time(*low),
+                format_time(*high),
+                m.times.len()
+            ))
+        );
+        // Error bar with whiskers
+        svg += &format!(
+            "<path d=\"M{0:.1} {1:.1}H{2:.1}M{0:.1} {3:.1}V{4:.1}M{2:.1} {3:.1}V{4:.1}\" stroke=\"#222\" fill=\"none\"/>",
+            label_width + low * scale,
+            middle,
+            label_width + high * scale,
+            middle - 5.0,
+            middle + 5.0
+        );
+        svg += &format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" dominant-baseline=\"middle\">{} ({:.2}×)</text>\n",
+            label_width + longest * scale + 8.0,
+            middle,
+            escape(&format_time(m.wall.mean)),
+            m.wall.mean / timed[0].1.wall.mean
+        );
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Renders a page with no external assets holding an overview heatmap, a bar chart per benchmark
+/// and the implementations with links to their websites.
+pub fn render_html(results: &Results) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Brainfuck benchmark results</title>\n<style>{}</style>\n</head>\n<body>\n<h1>Brainfuck benchmark results</h1>\n",
+        STYLE
+    );
+
+    html += "<h2>Overview</h2>\n<p>Mean time relative to the fastest implementation on each benchmark, ranked by geometric mean.</p>\n";
+    html += &render_heatmap(results);
+
+    for bench in &results.benchmarks {
+        html += &format!("<h2>{}</h2>\n", escape(&bench.benchmark));
+        html += &render_bar_chart(bench);
+        let failed: Vec<String> = bench
+            .implementations
+            .iter()
+            .filter(|r| r.status != Status::Ok)
+            .map(|r| {
+                format!(
+                    "<li><a href=\"{}\">{}</a>: <span class=\"failed\">{}</span> {}</li>",
+                    escape(&r.website),
+                    escape(&r.implementation),
+                    r.status.label(),
+                    escape(r.reason.as_ref().map_or("", |s| s.as_str()))
+                )
+            })
+            .collect();
+        if !failed.is_empty() {
+            html += &format!("<ul>\n{}\n</ul>\n", failed.join("\n"));
+        }
+    }
+    html += &format!(
+        "<p>Bars are mean times, interpreters in blue and compilers in orange. Error bars are bootstrapped {:.0}% confidence intervals of the mean.</p>\n",
+        CONFIDENCE * 100.0
+    );
+
+    html += "<h2>Implementations</h2>\n<table>\n<tr><th>Implementation</th><th>Kind</th><th>Revision</th></tr>\n";
+    for row in results.summary() {
+        let interpreted = results
+            .benchmarks
+            .iter()
+            .flat_map(|b| &b.implementations)
+            .find(|r| r.implementation == row.implementation)
+            .map_or(true, |r| r.interpreted);
+        html += &format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td><code>{}</code></td></tr>\n",
+            escape(&row.website),
+            escape(&row.implementation),
+            if interpreted {
+                "interpreter"
+            } else {
+                "compiler"
+            },
+            escape(
+                results
+                    .revisions
+                    .get(&row.implementation)
+                    .map_or("", |r| r.as_str())
+            )
+        );
+    }
+    html += "</table>\n";
+
+    if !results.stage_failures.is_empty() {
+        html += "<h2>Stage failures</h2>\n<ul>\n";
+        for f in &results.stage_failures {
+            html += &format!(
+                "<li>{}: <span class=\"failed\">{}</span>, log at <code>{}</code></li>\n",
+                escape(&f.implementation),
+                escape(&f.describe()),
+                escape(&f.log)
+            );
+        }
+        html += "</ul>\n";
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
@@ -0,0 +1,151 @@
+use std::io::{ErrorKind, Read, Write};
+
+/// Size of the tape in cells
+pub const TAPE_SIZE: usize = 30000;
+
+/// Plain, unoptimized brainfuck interpreter used as the reference for expected outputs.
+///
+/// Semantics: 8-bit wrapping cells, a fixed 30000 cell tape with the pointer starting at
+/// the leftmost cell, and the current cell is left unchanged on EOF. Moving off either end
+/// of the tape is an error.
+pub fn interpret<R: Read, W: Write>(
+    program: &[u8],
+    input: &mut R,
+    output: &mut W,
+) -> Result<(), String> {
+    let program: Vec<u8> = program
+        .iter()
+        .cloned()
+        .filter(|c| b"+-<>.,[]".contains(c))
+        .collect();
+
+    let mut jumps = vec![0_usize; program.len()];
+    let mut stack = Vec::new();
+    for (i, &c) in program.iter().enumerate() {
+        match c {
+            b'[' => stack.push(i),
+            b']' => {
+                let open = stack.pop().ok_or_else(|| String::from("unmatched ]"))?;
+                jumps[open] = i;
+                jumps[i] = open;
+            }
+            _ => {}
+        }
+    }
+    if !stack.is_empty() {
+        return Err(String::from("unmatched ["));
+    }
+
+    let mut tape = vec![0_u8; TAPE_SIZE];
+    let mut ptr = 0_usize;
+    let mut pc = 0_usize;
+    while pc < program.len() {
+        match program[pc] {
+            b'+' => tape[ptr] = tape[ptr].wrapping_add(1),
+            b'-' => tape[ptr] = tape[ptr].wrapping_sub(1),
+            b'>' => {
+                ptr += 1;
+                if ptr == TAPE_SIZE {
+                    return Err(String::from("tape pointer moved past the right end"));
+                }
+            }
+            b'<' => {
+                if ptr == 0 {
+                    return Err(String::from("tape pointer moved past the left end"));
+                }
+                ptr -= 1;
+            }
+            b'.' => output.write_all(&[tape[ptr]]).map_err(|e| e.to_string())?,
+            b',' => {
+                output.flush().map_err(|e| e.to_string())?;
+                let mut byte = [0_u8; 1];
+                loop {
+                    match input.read(&mut byte) {
+                        Ok(0) => break,
+                        Ok(_) => {
+                            tape[ptr] = byte[0];
+                            break;
+                        }
+                        Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e.to_string()),
+                    }
+                }
+            }
+            b'[' => {
+                if tape[ptr] == 0 {
+                    pc = jumps[pc];
+                }
+            }
+            b']' => {
+                if tape[ptr] != 0 {
+                    pc = jumps[pc];
+                }
+            }
+            _ => unreachable!(),
+        }
+        pc += 1;
+    }
+
+    output.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(program: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        let mut output = Vec::new();
+        interpret(program.as_bytes(), &mut &input[..], &mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn cells_wrap() {
+        assert_eq!(run("-.", b""), Ok(vec![255]));
+        assert_eq!(run(&format!("{}.", "+".repeat(256)), b""), Ok(vec![0]));
+        assert_eq!(run(&format!("{}.", "+".repeat(257)), b""), Ok(vec![1]));
+    }
+
+    #[test]
+    fn eof_leaves_cell_unchanged() {
+        assert_eq!(run("+++,.", b""), Ok(vec![3]));
+        assert_eq!(run(",.,.", b"a"), Ok(vec![b'a', b'a']));
+    }
+
+    #[test]
+    fn reads_input_and_ignores_comments() {
+        assert_eq!(run("copy , this . twice ,.", b"xy"), Ok(vec![b'x', b'y']));
+    }
+
+    #[test]
+    fn loops() {
+        // 3 * 4 into the second cell
+        assert_eq!(run("+++[>++++<-]>.", b""), Ok(vec![12]));
+        assert_eq!(run("[.]+.", b""), Ok(vec![1]));
+    }
+
+    #[test]
+    fn left_end_of_tape() {
+        assert_eq!(
+            run("<", b""),
+            Err(String::from("tape pointer moved past the left end"))
+        );
+    }
+
+    #[test]
+    fn right_end_of_tape() {
+        assert!(run(&">".repeat(TAPE_SIZE - 1), b"").is_ok());
+        assert_eq!(
+            run(&">".repeat(TAPE_SIZE), b""),
+            Err(String::from("tape pointer moved past the right end"))
+        );
+    }
+
+    #[test]
+    fn unmatched_brackets() {
+        assert_eq!(run("+[", b""), Err(String::from("unmatched [")));
+        assert_eq!(run("+]", b""), Err(String::from("unmatched ]")));
+        assert_eq!(run("[]]", b""), Err(String::from("unmatched ]")));
+        assert_eq!(run("[[]", b""), Err(String::from("unmatched [")));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Contents of `bf-lock.toml`, the exact upstream revision of each implementation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Commit checked out for each implementation, by name
+    #[serde(default)]
+    pub revisions: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    /// Loads the lockfile, or an empty one if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Lockfile::default());
+        }
+        let text =
+            read_to_string(path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        let text = format!(
+            "# Generated by `cargo run -- update`. Normal runs check out these revisions.\n{}",
+            text
+        );
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+    }
+}
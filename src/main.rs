@@ -1,23 +1,55 @@
 #![feature(proc_macro_hygiene, stmt_expr_attributes)]
 
 use bf_impl::*;
+use cache::*;
+use chart::*;
+use history::*;
+use html::*;
 use indoc::indoc;
 use itertools::Itertools;
+use lock::*;
 use regex::Regex;
+use results::*;
+use size::*;
+use state::*;
+use stats::*;
+use std::collections::HashMap;
 use std::env::current_dir;
-use std::fs::{copy, create_dir_all, read_dir, read_to_string, remove_dir_all, File};
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{exit, Command};
+use std::time::Duration;
 use structopt::StructOpt;
+use timing::*;
+use tools::*;
+use verify::*;
+use watchdog::*;
 
 mod bf_impl;
+mod cache;
+mod chart;
+mod history;
+mod html;
+mod interpreter;
+mod lock;
+mod results;
+mod size;
+mod state;
+mod stats;
+mod timing;
+mod tools;
+mod verify;
+mod watchdog;
 
 /// Master trait for all implementations
 trait BFImpl {
     /// Returns the name of the implementation
     fn name(&self) -> String;
 
+    /// Returns the website of the implementation
+    fn website(&self) -> String;
+
     /// Returns if it is an interpreter
     fn interpreted(&self) -> bool;
 
@@ -25,60 +57,106 @@ trait BFImpl {
     fn enabled(&self) -> bool;
 
     /// Stage for fetching the source/binary from the sky
-    fn get(&self);
+    fn get(&self) -> StageResult;
+
+    /// Fetches the newest upstream revision and returns it, for implementations that can be locked
+    fn update(&self) -> Result<Option<String>, String>;
+
+    /// Revision of the fetched source, if it is known
+    fn revision(&self) -> Option<String>;
+
+    /// Tools needed to fetch, build and prepare it
+    fn requirements(&self) -> Vec<Requirement>;
+
+    /// Everything that decides how it is built, such as its build steps. Used for build stamps.
+    fn build_recipe(&self) -> String;
 
     /// Stage for building the program itself
-    fn build(&self);
+    fn build(&self) -> StageResult;
 
     /// Stage for building any binaries with the program (for compilers, empty for interpreters)
-    fn prepare(&self, file: PathBuf);
+    fn prepare(&self, file: PathBuf) -> StageResult;
+
+    /// Executable produced by the prepare stage (for compilers, None for interpreters)
+    fn compiled_exe(&self) -> Option<PathBuf>;
+
+    /// Intermediate sources produced by the prepare stage, such as generated C
+    fn generated_sources(&self) -> Vec<PathBuf>;
+
+    /// Where the executable compiled from the benchmark is cached, for compilers. None if it can't
+    /// be cached, such as when the source revision isn't known.
+    fn cached_exe(&self, file: &Path) -> Option<PathBuf>;
 
     /// Run the brainfuck!
     fn get_invoke_command(&self, file: PathBuf) -> String;
-
-    /// Filter the output md file to use the proper program name
-    fn filter_output(&self, contents: String) -> String;
 }
 
-fn run_outputted_command(c: &mut Command) {
-    if !c.status().unwrap().success() {
-        exit(1);
+/// Result of a stage. The error holds a log of what went wrong.
+type StageResult = Result<(), String>;
+
+fn run_outputted_command(c: &mut Command) -> StageResult {
+    let status = c
+        .status()
+        .map_err(|e| format!("Command {:#?} failed to launch: {}", c, e))?;
+    if !status.success() {
+        return Err(format!("Command {:#?} output {:?}", c, status.code()));
     }
+    Ok(())
 }
 
-fn run_command(c: &mut Command) {
-    let command_output = c.output().unwrap();
+fn run_command_output(c: &mut Command) -> Result<Vec<u8>, String> {
+    let command_output = c
+        .output()
+        .map_err(|e| format!("Command {:#?} failed to launch: {}", c, e))?;
 
     if !command_output.status.success() {
-        println!(
-            "Command {:#?} output {}:\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
+        return Err(format!(
+            "Command {:#?} output {:?}:\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
             c,
-            command_output.status.code().unwrap(),
+            command_output.status.code(),
             String::from_utf8_lossy(&command_output.stdout),
             String::from_utf8_lossy(&command_output.stderr),
-        );
-        exit(1);
+        ));
     }
+
+    Ok(command_output.stdout)
 }
 
-fn run_command_with_pipe(c: &mut Command, output: &str) {
-    let command_output = c.output().unwrap();
+fn run_command(c: &mut Command) -> StageResult {
+    run_command_output(c).map(|_| ())
+}
 
-    if !command_output.status.success() {
-        println!(
-            "Command {:#?} output {}:\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
-            c,
-            command_output.status.code().unwrap(),
-            String::from_utf8(command_output.stdout).unwrap(),
-            String::from_utf8(command_output.stderr).unwrap()
-        );
-        exit(1);
-    } else {
-        File::create(output)
-            .unwrap()
-            .write_all(&command_output.stdout)
-            .unwrap();
+fn run_command_with_pipe(c: &mut Command, output: &str) -> StageResult {
+    let stdout = run_command_output(c)?;
+    File::create(output)
+        .and_then(|mut f| f.write_all(&stdout))
+        .map_err(|e| format!("Failed to write {}: {}", output, e))
+}
+
+/// Name of an implementation made safe for file names
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Converts an io error into a stage error
+fn io_error(e: std::io::Error) -> String {
+    e.to_string()
+}
+
+/// Command that runs the given string in the same shell hyperfine uses
+fn shell_command(command: &str) -> Command {
+    cfg_if::cfg_if! {
+        if #[cfg(windows)] {
+            let mut c = Command::new("powershell");
+            c.args(&["-Command", command]);
+        } else {
+            let mut c = Command::new("sh");
+            c.args(&["-c", command]);
+        }
     }
+    c
 }
 
 fn windows() -> bool {
@@ -91,55 +169,218 @@ fn windows() -> bool {
     }
 }
 
-fn git_repo(url: &str, folder: &str) {
+/// Points origin of an existing clone at the url, in case the source moved to a mirror
+fn git_set_origin(url: &str, folder: &str) -> StageResult {
+    run_command(
+        Command::new("git")
+            .args(&["remote", "set-url", "origin", url])
+            .current_dir(folder),
+    )
+}
+
+fn git_repo(url: &str, folder: &str) -> StageResult {
     let folder_path = PathBuf::from(&folder);
     if folder_path.exists() {
         assert_eq!(folder_path.is_dir(), true);
 
+        git_set_origin(url, folder)?;
         run_command(
             Command::new("git")
                 .args(&["pull", "--ff", "origin", "master"])
                 .current_dir(folder),
-        );
+        )
     } else {
-        run_command(Command::new("git").args(&["clone", &url, &folder]));
+        run_command(Command::new("git").args(&["clone", &url, &folder]))
     }
 }
 
-fn git_repo_branch(url: &str, folder: &str, branch: &str) {
+fn git_repo_branch(url: &str, folder: &str, branch: &str) -> StageResult {
     let folder_path = PathBuf::from(&folder);
     if folder_path.exists() {
         assert_eq!(folder_path.is_dir(), true);
 
+        git_set_origin(url, folder)?;
         run_command(
             Command::new("git")
                 .args(&["checkout", branch])
                 .current_dir(folder),
-        );
+        )
     } else {
-        run_command(Command::new("git").args(&["clone", url, folder, "--branch", branch]));
+        run_command(Command::new("git").args(&["clone", url, folder, "--branch", branch]))
+    }
+}
+
+/// Clones the repo if needed and checks out exactly the given commit
+fn git_checkout(url: &str, folder: &str, rev: &str) -> StageResult {
+    if Path::new(folder).exists() {
+        git_set_origin(url, folder)?;
+    } else {
+        run_command(Command::new("git").args(&["clone", url, folder]))?;
+    }
+    let commit = format!("{}^{{commit}}", rev);
+    if run_command(
+        Command::new("git")
+            .args(&["cat-file", "-e", &commit])
+            .current_dir(folder),
+    )
+    .is_err()
+    {
+        run_command(
+            Command::new("git")
+                .args(&["fetch", "origin"])
+                .current_dir(folder),
+        )?;
+    }
+    run_command(
+        Command::new("git")
+            .args(&["checkout", "-q", "--detach", rev])
+            .current_dir(folder),
+    )
+}
+
+/// Checks out the newest commit of the branch, master by default, and returns it
+fn git_update(url: &str, folder: &str, branch: Option<&str>) -> Result<String, String> {
+    if Path::new(folder).exists() {
+        git_set_origin(url, folder)?;
+    } else {
+        run_command(Command::new("git").args(&["clone", url, folder]))?;
+    }
+    run_command(
+        Command::new("git")
+            .args(&["fetch", "origin", branch.unwrap_or("master")])
+            .current_dir(folder),
+    )?;
+    run_command(
+        Command::new("git")
+            .args(&["checkout", "-q", "--detach", "FETCH_HEAD"])
+            .current_dir(folder),
+    )?;
+    git_revision(folder).ok_or_else(|| format!("Can't read the revision of {}", folder))
+}
+
+/// If the folder is the top level of its own git repo, rather than a folder inside another one
+fn git_toplevel(folder: &str) -> bool {
+    let toplevel = run_command_output(
+        Command::new("git")
+            .args(&["rev-parse", "--show-toplevel"])
+            .current_dir(folder),
+    );
+    match (toplevel, Path::new(folder).canonicalize()) {
+        (Ok(toplevel), Ok(folder)) => {
+            Path::new(String::from_utf8_lossy(&toplevel).trim())
+                .canonicalize()
+                .ok()
+                == Some(folder)
+        }
+        _ => false,
+    }
+}
+
+/// If tracked files in the repo have changes that aren't committed
+fn git_dirty(folder: &str) -> bool {
+    run_command_output(
+        Command::new("git")
+            .args(&["status", "--porcelain", "--untracked-files=no"])
+            .current_dir(folder),
+    )
+    .map(|o| !o.is_empty())
+    .unwrap_or(false)
+}
+
+/// Commit checked out in the repo
+fn git_revision(folder: &str) -> Option<String> {
+    run_command_output(
+        Command::new("git")
+            .args(&["rev-parse", "HEAD"])
+            .current_dir(folder),
+    )
+    .ok()
+    .map(|o| String::from_utf8_lossy(&o).trim().to_string())
+}
+
+/// Extracts a local tarball into the folder, unless that tarball was already extracted there. The
+/// hash of the extracted tarball is kept next to the folder, so a changed tarball replaces it.
+fn extract_tarball(tarball: &str, folder: &str, strip: usize) -> StageResult {
+    let stamp = format!("{}.sha256", folder.trim_end_matches('/'));
+    let hash = sha256_file(tarball)?;
+    if Path::new(folder).exists() {
+        if read_to_string(&stamp).ok().as_ref().map(|s| s.trim()) == Some(hash.as_str()) {
+            return Ok(());
+        }
+        remove_dir_all(folder).map_err(|e| format!("{}: {}", folder, e))?;
+    }
+    create_dir_all(folder).map_err(io_error)?;
+    let result = run_command(Command::new("tar").args(&[
+        "-xf",
+        tarball,
+        "-C",
+        folder,
+        &format!("--strip-components={}", strip),
+    ]));
+    if result.is_err() {
+        let _ = remove_dir_all(folder);
+        return result;
     }
+    std::fs::write(&stamp, hash).map_err(|e| format!("{}: {}", stamp, e))
 }
 
-fn curl_file(url: &str, dest: &str) {
+/// Recursively copies a folder, overwriting files that already exist
+fn copy_dir(from: &Path, to: &Path) -> StageResult {
+    create_dir_all(to).map_err(|e| format!("{}: {}", to.to_string_lossy(), e))?;
+    for entry in read_dir(from).map_err(|e| format!("{}: {}", from.to_string_lossy(), e))? {
+        let entry = entry.map_err(io_error)?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type().map_err(io_error)?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)
+                .map_err(|e| format!("{}: {}", entry.path().to_string_lossy(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn curl_file(url: &str, dest: &str) -> StageResult {
     if !Path::new(dest).exists() {
-        run_command(Command::new("curl").args(&["-L", url, "--output", dest]));
-        std::fs::metadata(&dest).unwrap().permissions();
+        run_command(Command::new("curl").args(&["-L", "--fail", url, "--output", dest]))?;
         cfg_if::cfg_if! {
             if #[cfg(not(windows))] {
                 use std::os::unix::fs::PermissionsExt;
-                let mut perms = std::fs::metadata(&dest).unwrap().permissions();
+                let mut perms = std::fs::metadata(&dest).map_err(io_error)?.permissions();
                 perms.set_mode(0o755);
-                std::fs::set_permissions(dest, perms).unwrap();
+                std::fs::set_permissions(dest, perms).map_err(io_error)?;
             }
         };
     }
+    Ok(())
 }
 
-fn create_cmake(name: &str, src_dir: &str, glob: &str) {
-    let files = glob::glob(glob).unwrap();
-    let p = PathBuf::new();
-    p.as_os_str().to_string_lossy().to_string();
+/// SHA-256 of a file as lowercase hex
+fn sha256_file(path: &str) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(format!("{:x}", hasher.result()))
+}
+
+/// Checks the file against the expected SHA-256
+fn verify_sha256(path: &str, expected: &str) -> StageResult {
+    let actual = sha256_file(path)?;
+    if actual != expected.trim().to_lowercase() {
+        return Err(format!(
+            "SHA-256 mismatch for {}:\n  expected {}\n  got      {}",
+            path,
+            expected.trim(),
+            actual
+        ));
+    }
+    Ok(())
+}
+
+fn create_cmake(name: &str, src_dir: &str, glob: &str) -> StageResult {
+    let files = glob::glob(glob).map_err(|e| e.to_string())?;
     let files_str = files
         .into_iter()
         .map(|f| {
@@ -168,30 +409,31 @@ fn create_cmake(name: &str, src_dir: &str, glob: &str) {
     let path = path_dsl::path!(src_dir | "CMakeLists.txt");
     if !path.exists() {
         File::create(&path_dsl::path!(src_dir | "CMakeLists.txt"))
-            .unwrap()
-            .write_all(cmake.as_bytes())
-            .unwrap();
+            .and_then(|mut f| f.write_all(cmake.as_bytes()))
+            .map_err(io_error)?;
     }
+    Ok(())
 }
 
-fn build_cmake(_name: &str, output_dir: &str, src_dir: &str) {
+fn build_cmake(_name: &str, output_dir: &str, src_dir: &str) -> StageResult {
     run_command(Command::new("cmake").args(&[
         "-S",
         src_dir,
         "-B",
         output_dir,
         "-DCMAKE_BUILD_TYPE=Release",
-    ]));
-    run_command(Command::new("cmake").args(&["--build", &output_dir, "--config", "release"]));
+    ]))?;
+    run_command(Command::new("cmake").args(&["--build", &output_dir, "--config", "release"]))?;
     #[cfg(target_os = "windows")]
     {
         let exe = format!("{}.exe", _name);
-        copy(
+        std::fs::copy(
             &path_dsl::path!(output_dir | "Release" | &exe),
             &path_dsl::path!(output_dir | exe),
         )
-        .unwrap();
+        .map_err(io_error)?;
     }
+    Ok(())
 }
 
 /// A program to benchmark various different brainfuck implementations,
@@ -199,32 +441,163 @@ fn build_cmake(_name: &str, output_dir: &str, src_dir: &str) {
 #[structopt(name = "brainfuck-benchmark")]
 struct Options {
     /// Regex to select which implementations to run
-    #[structopt(short, long = "impls")]
+    #[structopt(short, long = "impls", global = true)]
     impl_regex: Option<String>,
 
     /// Regex to select which implementations not to run
-    #[structopt(long = "ignore-impls", alias = "ni")]
+    #[structopt(long = "ignore-impls", alias = "ni", global = true)]
     negative_impl_regex: Option<String>,
 
     /// Regex to select which benchmarks to run
-    #[structopt(short, long = "benches")]
+    #[structopt(short, long = "benches", global = true)]
     bench_regex: Option<String>,
 
     /// Regex to select which benchmarks to not run
-    #[structopt(long = "ignore-benches", alias = "nb")]
+    #[structopt(long = "ignore-benches", alias = "nb", global = true)]
     negative_bench_regex: Option<String>,
 
     /// Don't run interpreters
-    #[structopt(long)]
+    #[structopt(long, global = true)]
     no_interpreters: bool,
 
     /// Don't run compilers
-    #[structopt(long)]
+    #[structopt(long, global = true)]
     no_compilers: bool,
 
     /// Clean all temporary data and quit
     #[structopt(long)]
     clean: bool,
+
+    /// Program used to time the implementations: native or hyperfine
+    #[structopt(long, default_value = "native", possible_values = &["native", "hyperfine"], global = true)]
+    backend: TimingBackend,
+
+    /// Untimed runs of each implementation before measuring
+    #[structopt(long, default_value = "0", global = true)]
+    warmup: usize,
+
+    /// Timed runs of each implementation
+    #[structopt(short, long, default_value = "3", global = true)]
+    runs: usize,
+
+    /// Keep timing each implementation until the coefficient of variation of its mean (standard
+    /// error / mean) drops below this many percent, with --runs as the minimum. Native backend only.
+    #[structopt(long, parse(try_from_str = parse_target_cv), global = true)]
+    target_cv: Option<f64>,
+
+    /// Most seconds of timed runs per implementation and benchmark when sampling adaptively
+    #[structopt(long, default_value = "30", parse(try_from_str = parse_time_budget), global = true)]
+    time_budget: f64,
+
+    /// Most timed runs per implementation and benchmark when sampling adaptively
+    #[structopt(long, default_value = "100", global = true)]
+    max_runs: usize,
+
+    /// Times the prepare stage of compilers this many times per benchmark
+    #[structopt(long, default_value = "1", global = true)]
+    compile_runs: usize,
+
+    /// Kill a run after this many seconds and record it as a timeout. Either SECONDS for every
+    /// benchmark or BENCH=SECONDS for one of them. Can be given multiple times. The hyperfine backend
+    /// only enforces it on the verification run.
+    #[structopt(long = "timeout", number_of_values = 1, global = true)]
+    timeouts: Vec<TimeoutSpec>,
+
+    /// Regenerate the expected outputs of the selected benchmarks with the reference interpreter and quit
+    #[structopt(long)]
+    bless: bool,
+
+    /// Manifest describing the implementations to benchmark
+    #[structopt(
+        long,
+        default_value = "bf-impls.toml",
+        parse(from_os_str),
+        global = true
+    )]
+    manifest: PathBuf,
+
+    /// Per-implementation source overrides, such as local mirrors, tarballs or folders
+    #[structopt(
+        long,
+        default_value = "bf-sources.toml",
+        parse(from_os_str),
+        global = true
+    )]
+    sources: PathBuf,
+
+    /// Build implementations even if their build stamp says they are up to date
+    #[structopt(long, global = true)]
+    rebuild: bool,
+
+    /// Lockfile holding the upstream revision of each implementation
+    #[structopt(
+        long,
+        default_value = "bf-lock.toml",
+        parse(from_os_str),
+        global = true
+    )]
+    lockfile: PathBuf,
+
+    /// Archived run to check this run against. The run exits with an error if anything got slower
+    /// than --max-slowdown or significantly slower at --significance.
+    #[structopt(long, global = true)]
+    baseline: Option<String>,
+
+    /// Largest slowdown against the baseline in percent
+    #[structopt(long, default_value = "5", global = true)]
+    max_slowdown: f64,
+
+    /// p-value of Welch's t-test below which any slowdown against the baseline is a regression
+    #[structopt(long, default_value = "0.01", global = true)]
+    significance: f64,
+
+    #[structopt(subcommand)]
+    command: Option<Subcommand>,
+
+    /// Run the given file with the reference interpreter and quit
+    #[structopt(long, hidden = true, parse(from_os_str))]
+    interpret: Option<PathBuf>,
+}
+
+#[derive(StructOpt, PartialEq)]
+enum Subcommand {
+    /// Fetch the newest upstream revision of the selected implementations and write them to the lockfile
+    Update,
+    /// Check that the tools every selected implementation needs are installed
+    Doctor,
+    /// List the selected implementations and benchmarks
+    List,
+    /// Fetch the selected implementations
+    Fetch,
+    /// Build the selected implementations, fetching any that weren't yet
+    Build,
+    /// Run the benchmarks, fetching and building any implementations that weren't yet
+    Run,
+    /// Regenerate the reports from the saved results without running anything
+    Report,
+    /// Compare two archived runs, by id, unique id prefix, `latest`, `previous` or path
+    Compare { run_a: String, run_b: String },
+}
+
+/// Runs a brainfuck file through the reference interpreter on the process' stdin/stdout.
+fn interpret_file(file: &Path) -> Result<(), String> {
+    let program = std::fs::read(file).map_err(|e| e.to_string())?;
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut output = std::io::BufWriter::new(stdout.lock());
+    interpreter::interpret(&program, &mut stdin.lock(), &mut output)
+}
+
+/// Runs a benchmark through the reference interpreter and writes its expected output file.
+fn bless_file(file: &Path) -> Result<(), String> {
+    let program = std::fs::read(file).map_err(|e| e.to_string())?;
+    let mut input: Box<dyn std::io::Read> = match input_path(file) {
+        Some(path) => Box::new(File::open(path).map_err(|e| e.to_string())?),
+        None => Box::new(std::io::empty()),
+    };
+    let mut output = Vec::new();
+    interpreter::interpret(&program, &mut input, &mut output)?;
+    std::fs::write(expected_output_path(file), output).map_err(|e| e.to_string())
 }
 
 fn main() {
@@ -236,6 +609,14 @@ fn main() {
         exit(0);
     }
 
+    if let Some(file) = &opt.interpret {
+        if let Err(e) = interpret_file(file) {
+            eprintln!("{}: {}", file.to_string_lossy(), e);
+            exit(1);
+        }
+        exit(0);
+    }
+
     let impl_regex = opt.impl_regex.as_ref().map(|s| Regex::new(&s).unwrap());
     let negative_impl_regex = opt
         .negative_impl_regex
@@ -247,25 +628,95 @@ fn main() {
         .as_ref()
         .map(|s| Regex::new(&s).unwrap());
 
+    if let Some(Subcommand::Compare { run_a, run_b }) = &opt.command {
+        let (a, b) = match (ArchivedRun::load(run_a), ArchivedRun::load(run_b)) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("{}", e);
+                exit(1);
+            }
+        };
+        compare_runs(
+            &a,
+            &b,
+            |r| {
+                impl_regex
+                    .as_ref()
+                    .map_or(true, |re| re.is_match(&r.implementation))
+                    && negative_impl_regex
+                        .as_ref()
+                        .map_or(true, |re| !re.is_match(&r.implementation))
+                    && if r.interpreted {
+                        !opt.no_interpreters
+                    } else {
+                        !opt.no_compilers
+                    }
+            },
+            |bench| {
+                bench_regex.as_ref().map_or(true, |re| re.is_match(bench))
+                    && negative_bench_regex
+                        .as_ref()
+                        .map_or(true, |re| !re.is_match(bench))
+            },
+        );
+        exit(0);
+    }
+
+    let timing_options = TimingOptions {
+        warmup: opt.warmup,
+        runs: opt.runs,
+        timeout: None,
+        adaptive: opt.target_cv.map(|cv| AdaptiveSampling {
+            target_cv: cv / 100.0,
+            budget: Duration::from_secs_f64(opt.time_budget),
+            max_runs: opt.max_runs,
+        }),
+    };
+
     create_dir_all("build/src").unwrap();
     create_dir_all("build/out").unwrap();
     create_dir_all("results").unwrap();
 
-    let mut bf: Vec<Box<dyn BFImpl + Send + Sync>> = vec![
-        Box::new(ApankratBffBfImpl),
-        Box::new(AsumagicAshbfBfImpl),
-        Box::new(CwfitzgeraldBfccBfImpl),
-        Box::new(CwfitzgeraldBfccOldBfImpl),
-        Box::new(DethraidBrainfuckBfImpl),
-        Box::new(GardrekStvmBfImpl),
-        Box::new(KotayBffsreeBfImpl),
-        Box::new(LifthrasiirEsotopeBfImpl),
-        Box::new(RdebathTritiumBfImpl(RdebathTritiumMode::ArrayInterpreter)),
-        Box::new(RdebathTritiumBfImpl(RdebathTritiumMode::DynASM)),
-        Box::new(RdebathTritiumBfImpl(RdebathTritiumMode::LightningJIT)),
-        Box::new(RinoldmSbfiBfImpl),
-        Box::new(WilfredBfcBfImpl),
-    ];
+    let manifest = match load_manifest(&opt.manifest) {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Failed to load manifest: {}", e);
+            exit(1);
+        }
+    };
+    let overrides = match SourceOverrides::load(&opt.sources) {
+        Ok(o) => o,
+        Err(e) => {
+            println!("Failed to load source overrides: {}", e);
+            exit(1);
+        }
+    };
+    let mut lockfile = match Lockfile::load(&opt.lockfile) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("Failed to load lockfile: {}", e);
+            exit(1);
+        }
+    };
+
+    let manifest = match manifest
+        .into_iter()
+        .map(|i| i.overridden(&overrides))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Failed to apply source overrides: {}", e);
+            exit(1);
+        }
+    };
+
+    let mut bf: Vec<Box<dyn BFImpl + Send + Sync>> = vec![Box::new(BuiltinReferenceBfImpl)];
+    bf.extend(
+        manifest
+            .into_iter()
+            .map(|i| Box::new(i.locked(&lockfile)) as Box<dyn BFImpl + Send + Sync>),
+    );
     bf.sort_unstable_by_key(|v| v.name());
     bf.retain(|v| {
         let enabled = v.enabled();
@@ -283,8 +734,68 @@ fn main() {
         };
         enabled && regex_enabled && negative_regex_enabled && type_enabled
     });
-    bf.iter()
-        .for_each(|v| println!("Implemenation: {}", v.name()));
+
+    if let Some(Subcommand::List) = opt.command {
+        let state = State::load();
+        let width = bf.iter().map(|b| b.name().len()).max().unwrap_or(0);
+        println!("Implementations:");
+        for b in &bf {
+            println!(
+                "  {:<width$}  {:<11}  {:<7}  {}",
+                b.name(),
+                if b.interpreted() {
+                    "interpreter"
+                } else {
+                    "compiler"
+                },
+                state.describe(&b.name()),
+                b.website(),
+                width = width
+            );
+        }
+    } else {
+        bf.iter()
+            .for_each(|v| println!("Implemenation: {}", v.name()));
+    }
+
+    if let Some(Subcommand::Doctor) = opt.command {
+        exit(if doctor(&bf, opt.backend) { 0 } else { 1 });
+    }
+
+    let mut toolbox = Toolbox::default();
+    if opt.backend == TimingBackend::Hyperfine {
+        if let Err(reason) = toolbox.check(&Requirement::tool("hyperfine")) {
+            println!("--backend hyperfine can't be used: {}", reason);
+            exit(1);
+        }
+        if opt.target_cv.is_some() {
+            println!("--target-cv needs the native backend");
+            exit(1);
+        }
+    }
+
+    if let Some(Subcommand::Update) = opt.command {
+        let mut failed = false;
+        for b in &bf {
+            println!("Updating {}", b.name());
+            match b.update() {
+                Ok(Some(rev)) => {
+                    println!("  {}", rev);
+                    lockfile.revisions.insert(b.name(), rev);
+                }
+                Ok(None) => {}
+                Err(log) => {
+                    println!("Updating {} failed:\n{}", b.name(), log);
+                    failed = true;
+                }
+            }
+        }
+        if let Err(e) = lockfile.write(&opt.lockfile) {
+            println!("Failed to write lockfile: {}", e);
+            exit(1);
+        }
+        exit(if failed { 1 } else { 0 });
+    }
 
     let mut benches: Vec<_> = read_dir("benches")
         .unwrap()
@@ -293,6 +804,7 @@ fn main() {
         .collect();
     benches.sort_unstable_by_key(|v| v.file_name());
     benches.retain(|b| {
+        let is_bench = b.path().extension().map_or(false, |e| e == "b");
         let regex_enabled = match &bench_regex {
             Some(r) => r.is_match(&b.file_name().to_string_lossy()),
             None => true,
@@ -301,26 +813,228 @@ fn main() {
             Some(r) => !r.is_match(&b.file_name().to_string_lossy()),
             None => true,
         };
-        regex_enabled && negative_regex_enabled
+        is_bench && regex_enabled && negative_regex_enabled
     });
 
+    if let Some(Subcommand::List) = opt.command {
+        println!("Benchmarks:");
+        benches
+            .iter()
+            .for_each(|b| println!("  {}", b.file_name().to_string_lossy()));
+        exit(0);
+    }
+
     benches
         .iter()
         .for_each(|b| println!("Benchmark: {}", b.file_name().to_string_lossy()));
 
-    for b in &bf {
-        println!("Fetching {}", b.name());
+    if opt.bless {
+        for bench in &benches {
+            println!("Blessing {}", bench.path().to_string_lossy());
+            if let Err(e) = bless_file(&bench.path()) {
+                println!("{}: {}", bench.path().to_string_lossy(), e);
+                exit(1);
+            }
+        }
+        exit(0);
+    }
 
-        b.get();
+    if let Some(Subcommand::Report) = opt.command {
+        match Results::read_json(SAVED_RESULTS_PATH) {
+            Ok(results) => {
+                write_reports(&results);
+                results.print_stage_failures();
+                exit(0);
+            }
+            Err(e) => {
+                println!(
+                    "No saved results to report, run the benchmarks first: {}",
+                    e
+                );
+                exit(1);
+            }
+        }
     }
 
+    let baseline = opt.baseline.as_ref().map(|spec| {
+        ArchivedRun::load(spec).unwrap_or_else(|e| {
+            println!("Failed to load the baseline: {}", e);
+            exit(1);
+        })
+    });
+
+    let mut state = State::load();
+    let attempted: Vec<String> = bf.iter().map(|b| b.name()).collect();
+
+    bf.retain(|b| match toolbox.check_all(&**b) {
+        Ok(()) => {
+            state.succeeded(&b.name(), "requirements");
+            true
+        }
+        Err(reason) => {
+            println!("Skipping {}: {}", b.name(), reason);
+            state.failed(StageFailure::record(&**b, "requirements", None, &reason));
+            false
+        }
+    });
+
+    // fetch and build redo their stage, later stages only catch up on what is missing
+    let command = opt.command.as_ref();
+    let refetch = command.is_none() || command == Some(&Subcommand::Fetch);
+    run_stage(
+        &mut bf,
+        &mut state,
+        "fetch",
+        |state, name| refetch || !state.fetched.contains(name),
+        |b| {
+            println!("Fetching {}", b.name());
+            b.get()
+        },
+    );
+    if command == Some(&Subcommand::Fetch) {
+        print_failures(&state, &attempted);
+        exit(if failed_until(&state, &attempted, "fetch") {
+            1
+        } else {
+            0
+        });
+    }
+
+    let stamps: HashMap<String, BuildStamp> = bf
+        .iter()
+        .map(|b| (b.name(), BuildStamp::new(&**b, &mut toolbox)))
+        .collect();
+    run_stage(
+        &mut bf,
+        &mut state,
+        "build",
+        |state, name| {
+            let up_to_date =
+                state.built.contains(name) && stamps[name].matches(state.stamps.get(name));
+            if up_to_date && !opt.rebuild {
+                println!("{} is up to date, not building it", name);
+            }
+            opt.rebuild || !up_to_date
+        },
+        |b| {
+            println!("Building {}", b.name());
+            b.build()
+        },
+    );
     for b in &bf {
-        println!("Building {}", b.name());
+        state.stamps.insert(b.name(), stamps[&b.name()].clone());
+    }
+    state.save();
+    if command == Some(&Subcommand::Build) {
+        print_failures(&state, &attempted);
+        exit(if failed_until(&state, &attempted, "build") {
+            1
+        } else {
+            0
+        });
+    }
+
+    let environment = Environment::capture(&opt);
+    let mut new_results = run_benchmarks(&bf, benches, &opt, timing_options);
+    let mut stage_failures: Vec<StageFailure> = state
+        .failures
+        .iter()
+        .filter(|f| attempted.contains(&f.implementation))
+        .cloned()
+        .collect();
+    stage_failures.append(&mut new_results.stage_failures);
+    new_results.stage_failures = stage_failures;
+
+    let gated = baseline.as_ref().map(|_| new_results.clone());
+    match ArchivedRun::archive(environment, new_results.clone()) {
+        Ok(run) => println!("Archived this run as {}", run.id),
+        Err(e) => println!("Failed to archive this run: {}", e),
+    }
 
-        b.build();
+    let mut results = Results::read_json(SAVED_RESULTS_PATH).unwrap_or_default();
+    results.merge(new_results, &attempted);
+    if let Err(e) = results.write_json(SAVED_RESULTS_PATH) {
+        println!("Failed to save results: {}", e);
     }
 
-    let mut full_output = String::new();
+    write_reports(&results);
+    results.print_stage_failures();
+
+    if let (Some(baseline), Some(current)) = (&baseline, &gated) {
+        let thresholds = GateThresholds {
+            max_slowdown: opt.max_slowdown,
+            significance: opt.significance,
+        };
+        if !regression_gate(baseline, current, thresholds) {
+            exit(1);
+        }
+    }
+}
+
+/// Runs a stage for the implementations that need it. The ones that fail are dropped.
+fn run_stage(
+    bf: &mut Vec<Box<dyn BFImpl + Send + Sync>>,
+    state: &mut State,
+    stage: &str,
+    needed: impl Fn(&State, &str) -> bool,
+    run: impl Fn(&dyn BFImpl) -> StageResult,
+) {
+    bf.retain(|b| {
+        if !needed(state, &b.name()) {
+            return true;
+        }
+        match run(&**b) {
+            Ok(()) => {
+                state.succeeded(&b.name(), stage);
+                true
+            }
+            Err(log) => {
+                println!("{} of {} failed, skipping it", stage, b.name());
+                state.failed(StageFailure::record(&**b, stage, None, &log));
+                false
+            }
+        }
+    });
+    state.save();
+}
+
+/// Prints the stage failures of the given implementations
+fn print_failures(state: &State, implementations: &[String]) {
+    Results {
+        stage_failures: state
+            .failures
+            .iter()
+            .filter(|f| implementations.contains(&f.implementation))
+            .cloned()
+            .collect(),
+        ..Results::default()
+    }
+    .print_stage_failures();
+}
+
+/// If any of the implementations failed the stage or one before it, so it didn't get through it
+fn failed_until(state: &State, implementations: &[String], stage: &str) -> bool {
+    const STAGES: [&str; 3] = ["requirements", "fetch", "build"];
+    let last = STAGES.iter().position(|s| *s == stage).unwrap();
+    state.failures.iter().any(|f| {
+        implementations.contains(&f.implementation) && STAGES[..=last].contains(&f.stage.as_str())
+    })
+}
+
+/// Prepares, verifies and times the implementations on every benchmark.
+fn run_benchmarks(
+    bf: &[Box<dyn BFImpl + Send + Sync>],
+    benches: Vec<std::fs::DirEntry>,
+    opt: &Options,
+    timing_options: TimingOptions,
+) -> Results {
+    let mut results = Results::default();
+
+    for b in bf {
+        if let Some(rev) = b.revision() {
+            results.revisions.insert(b.name(), rev);
+        }
+    }
 
     for bench in benches {
         let rel_path = bench.path().to_string_lossy().to_string();
@@ -331,60 +1045,207 @@ fn main() {
         println!("==========================================");
         println!("Starting benchmark {}\n", rel_path);
 
-        for b in &bf {
+        let mut bench_results = BenchmarkResults {
+            benchmark: file_name.clone(),
+            implementations: Vec::new(),
+        };
+
+        let mut prepared = Vec::new();
+        let mut compile_timings = HashMap::new();
+        let mut exe_sizes = HashMap::new();
+        for b in bf {
+            let cached_exe = b.cached_exe(&full_path);
+            if let Some(artifact) = cached_exe.as_ref().and_then(|exe| load_artifact(exe)) {
+                println!("Using {} compiled by {} from the cache", rel_path, b.name());
+                if let Some(c) = artifact.compile {
+                    compile_timings.insert(b.name(), c);
+                }
+                if let Some(size) = artifact.size {
+                    exe_sizes.insert(b.name(), size);
+                }
+                prepared.push(b);
+                continue;
+            }
+
             if !b.interpreted() {
                 println!("Compiling {} using {}", rel_path, b.name());
             }
-            b.prepare(full_path.clone());
+            let prepare = measure_prepare(&**b, &full_path, opt.compile_runs).and_then(|compile| {
+                let size = measure_size(&**b).unwrap_or_else(|e| {
+                    println!("Failed to measure executable size: {}", e);
+                    None
+                });
+                let artifact = CachedArtifact { compile, size };
+                if let Some(exe) = &cached_exe {
+                    store_artifact(&**b, exe, &artifact)?;
+                }
+                Ok(artifact)
+            });
+            match prepare {
+                Ok(artifact) => {
+                    if let Some(c) = artifact.compile {
+                        println!(
+                            "  Compile time (mean ± σ): {:.4} s ± {:.4} s    {} runs",
+                            c.wall.mean,
+                            c.wall.stddev,
+                            c.times.len()
+                        );
+                        compile_timings.insert(b.name(), c);
+                    }
+                    if let Some(size) = artifact.size {
+                        exe_sizes.insert(b.name(), size);
+                    }
+                    prepared.push(b);
+                }
+                Err(log) => {
+                    println!("Preparing {} failed, skipping it", b.name());
+                    let failure = StageFailure::record(&**b, "prepare", Some(&file_stem), &log);
+                    bench_results
+                        .implementations
+                        .push(ImplementationResult::failed(
+                            &**b,
+                            Failure::new(
+                                Status::Failed,
+                                format!("prepare failed, see {}", failure.log),
+                            ),
+                        ));
+                    results.stage_failures.push(failure);
+                }
+            }
         }
 
-        let result_md = format!("results/{}.md", file_stem);
-        cfg_if::cfg_if! {
-            if #[cfg(windows)] {
-                let extra = vec![
-                    "--show-output".into(),
-                    "-m".into(),
-                    "3".into(),
-                    "--export-markdown".into(),
-                    result_md.clone(),
-                    "--shell".into(),
-                    "powershell".into()
-                ];
-             }
-             else {
-                let extra = vec![
-                    "--show-output".into(),
-                    "-m".into(),
-                    "3".into(),
-                    "--export-markdown".into(),
-                    result_md.clone(),
-                ];
-             }
+        let input_buf = input_path(&full_path);
+        let input = input_buf.as_ref().map(|p| p.as_path());
+
+        let timeout = timeout_for(&opt.timeouts, &full_path);
+        let bench_timing_options = TimingOptions {
+            timeout,
+            ..timing_options
+        };
+
+        let expected = expected_output(&full_path);
+        if expected.is_none() {
+            println!(
+                "No expected output at {}, skipping verification",
+                expected_output_path(&full_path).to_string_lossy()
+            );
         }
-        let v: Vec<String> = bf
+
+        let mut passing = Vec::new();
+        for b in prepared {
+            if let Some(expected) = &expected {
+                println!("Verifying output of {}", b.name());
+                if let Err(failure) = verify_output(&**b, &full_path, input, expected, timeout) {
+                    println!("{} failed: {}", b.name(), failure.reason);
+                    bench_results
+                        .implementations
+                        .push(ImplementationResult::failed(&**b, failure));
+                    continue;
+                }
+            }
+            passing.push(b);
+        }
+
+        let commands: Vec<String> = passing
             .iter()
             .map(|b| b.get_invoke_command(full_path.clone()))
-            .chain(extra.into_iter())
             .collect();
 
         println!("Benchmarking...");
 
-        run_command(Command::new("hyperfine").args(&v));
-
-        let mut output_file = read_to_string(result_md).unwrap();
-
-        for b in &bf {
-            println!("Filtering output for {}", b.name());
-            output_file = b.filter_output(output_file);
+        match opt.backend {
+            TimingBackend::Native => {
+                for (b, c) in passing.iter().zip(commands.iter()) {
+                    println!("Timing {}", b.name());
+                    match measure(c, input, &bench_timing_options) {
+                        Ok(m) => {
+                            println!("{}", m.summary());
+                            bench_results
+                                .implementations
+                                .push(ImplementationResult::timed(&***b, m));
+                        }
+                        Err(failure) => {
+                            println!("{} failed: {}", b.name(), failure.reason);
+                            bench_results
+                                .implementations
+                                .push(ImplementationResult::failed(&***b, failure));
+                        }
+                    }
+                }
+            }
+            TimingBackend::Hyperfine => {
+                if !passing.is_empty() {
+                    let result_json = format!("results/{}.json", file_stem);
+                    match measure_hyperfine(&commands, input, &timing_options, &result_json) {
+                        Ok(measurements) => {
+                            for (b, m) in passing.iter().zip(measurements.into_iter()) {
+                                println!("{}\n{}", b.name(), m.summary());
+                                bench_results
+                                    .implementations
+                                    .push(ImplementationResult::timed(&***b, m));
+                            }
+                        }
+                        Err(log) => {
+                            println!("hyperfine failed:\n{}", log);
+                            for b in &passing {
+                                bench_results
+                                    .implementations
+                                    .push(ImplementationResult::failed(
+                                        &***b,
+                                        Failure::new(
+                                            Status::Failed,
+                                            String::from("hyperfine failed"),
+                                        ),
+                                    ));
+                            }
+                        }
+                    }
+                }
+            }
         }
 
-        full_output += &format!("# {}\n{}", file_name, output_file);
+        for r in &mut bench_results.implementations {
+            if r.status == Status::Ok {
+                r.compile = compile_timings.remove(&r.implementation);
+            }
+            r.size = exe_sizes.remove(&r.implementation);
+        }
+        bench_results
+            .implementations
+            .sort_by(|a, b| a.implementation.cmp(&b.implementation));
 
         println!("\nBenchmark finished!");
+        print_benchmark_chart(&bench_results);
+
+        results.benchmarks.push(bench_results);
+    }
+
+    print_overall_chart(&results);
+    results
+}
+
+/// Writes the markdown, JSON and CSV reports under `results/`
+fn write_reports(results: &Results) {
+    for b in &results.benchmarks {
+        let stem = Path::new(&b.benchmark)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        File::create(format!("results/{}.md", stem))
+            .unwrap()
+            .write_all(b.render_markdown().as_bytes())
+            .unwrap();
     }
 
     File::create("results/full.md")
         .unwrap()
-        .write_all(full_output.as_bytes())
+        .write_all(results.render_markdown().as_bytes())
+        .unwrap();
+    File::create("results/index.html")
+        .unwrap()
+        .write_all(render_html(results).as_bytes())
         .unwrap();
+    results.write_json("results/results.json").unwrap();
+    results.write_csv("results/results.csv").unwrap();
 }
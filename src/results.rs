@@ -0,0 +1,780 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Outcome of running one implementation on one benchmark
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Correct output, timed successfully
+    Ok,
+    /// Crashed or exited with a non-zero status
+    Failed,
+    /// Printed something other than the expected output
+    WrongOutput,
+    /// Killed after running longer than the timeout
+    Timeout,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Failed => "failed",
+            Status::WrongOutput => "wrong_output",
+            Status::Timeout => "timeout",
+        }
+    }
+
+    /// How the status is shown in reports
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Failed => "FAILED",
+            Status::WrongOutput => "WRONG OUTPUT",
+            Status::Timeout => "TIMEOUT",
+        }
+    }
+}
+
+/// Why an implementation could not be timed
+#[derive(Clone, Debug)]
+pub struct Failure {
+    pub status: Status,
+    pub reason: String,
+}
+
+impl Failure {
+    pub fn new(status: Status, reason: String) -> Self {
+        Failure { status, reason }
+    }
+}
+
+/// Result of one implementation on one benchmark
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImplementationResult {
+    pub implementation: String,
+    pub website: String,
+    pub interpreted: bool,
+    pub status: Status,
+    /// Set if the status isn't `Ok`
+    pub reason: Option<String>,
+    /// Set if the status is `Ok`
+    pub measurement: Option<Measurement>,
+    /// Time spent preparing the benchmark, for compilers
+    #[serde(default)]
+    pub compile: Option<CompileTiming>,
+    /// Size of the executable produced by the prepare stage, for compilers
+    #[serde(default)]
+    pub size: Option<ExeSize>,
+}
+
+impl ImplementationResult {
+    pub fn timed(b: &dyn BFImpl, measurement: Measurement) -> Self {
+        ImplementationResult {
+            implementation: b.name(),
+            website: b.website(),
+            interpreted: b.interpreted(),
+            status: Status::Ok,
+            reason: None,
+            measurement: Some(measurement),
+            compile: None,
+            size: None,
+        }
+    }
+
+    pub fn failed(b: &dyn BFImpl, failure: Failure) -> Self {
+        ImplementationResult {
+            implementation: b.name(),
+            website: b.website(),
+            interpreted: b.interpreted(),
+            status: failure.status,
+            reason: Some(failure.reason),
+            measurement: None,
+            compile: None,
+            size: None,
+        }
+    }
+
+    /// Mean compile time plus mean run time
+    pub fn total_time(&self) -> Option<f64> {
+        let run = self.measurement.as_ref()?.wall.mean;
+        Some(run + self.compile.as_ref().map_or(0.0, |c| c.wall.mean))
+    }
+
+    /// Markdown link to the implementation's website
+    pub fn link(&self) -> String {
+        format!("[`{}`]({})", self.implementation, self.website)
+    }
+}
+
+/// Confidence level of the intervals around mean times
+pub const CONFIDENCE: f64 = 0.95;
+/// p-value below which two implementations are told apart
+pub const SIGNIFICANCE: f64 = 0.05;
+
+/// Where an implementation ranks on a benchmark by mean time
+#[derive(Clone, Debug)]
+pub struct Rank {
+    /// 1 for the fastest. Implementations tied with the next faster one share its position.
+    pub position: usize,
+    /// Mann-Whitney U p-value against the next faster implementation
+    pub p_value: Option<f64>,
+    /// If other implementations share the position
+    pub shared: bool,
+    /// If there are too few runs for the test against the next faster implementation to ever be
+    /// significant, so neither a tie nor a difference was established
+    pub too_few_runs: bool,
+}
+
+impl Rank {
+    /// Position, with `=` in front if it is shared
+    pub fn label(&self) -> String {
+        format!("{}{}", if self.shared { "=" } else { "" }, self.position)
+    }
+
+    /// p-value against the next faster implementation, or why there is none worth giving
+    pub fn describe_p_value(&self) -> String {
+        match self.p_value {
+            Some(_) if self.too_few_runs => String::from("too few runs"),
+            Some(p) => format!("{:.3}", p),
+            None => String::from("-"),
+        }
+    }
+}
+
+/// Results of all implementations on one benchmark
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchmarkResults {
+    /// File name of the benchmark
+    pub benchmark: String,
+    pub implementations: Vec<ImplementationResult>,
+}
+
+impl BenchmarkResults {
+    /// Implementations which were timed, along with their measurement
+    pub fn timed(&self) -> impl Iterator<Item = (&ImplementationResult, &Measurement)> {
+        self.implementations
+            .iter()
+            .filter_map(|r| r.measurement.as_ref().map(|m| (r, m)))
+    }
+
+    /// Ranks the timed implementations by mean time. Neighbours which aren't significantly
+    /// different share a position.
+    pub fn ranks(&self) -> HashMap<&str, Rank> {
+        let mut timed: Vec<_> = self.timed().collect();
+        timed.sort_by(|a, b| a.1.wall.mean.partial_cmp(&b.1.wall.mean).unwrap());
+
+        let mut ranks: Vec<(&str, Rank)> = Vec::new();
+        for (i, (r, m)) in timed.iter().enumerate() {
+            let (p_value, too_few_runs) = if i > 0 {
+                let faster = &timed[i - 1].1.times;
+                (
+                    mann_whitney_u(faster, &m.times),
+                    mann_whitney_min_p(faster.len(), m.times.len()) >= SIGNIFICANCE,
+                )
+            } else {
+                (None, false)
+            };
+            let tied = !too_few_runs && p_value.map_or(false, |p| p >= SIGNIFICANCE);
+            let position = match ranks.last_mut() {
+                Some((_, previous)) if tied => {
+                    previous.shared = true;
+                    previous.position
+                }
+                _ => i + 1,
+            };
+            ranks.push((
+                r.implementation.as_str(),
+                Rank {
+                    position,
+                    p_value,
+                    shared: tied,
+                    too_few_runs,
+                },
+            ));
+        }
+        ranks.into_iter().collect()
+    }
+
+    /// Renders the timing table, followed by any implementations that couldn't be timed.
+    pub fn render_markdown(&self) -> String {
+        let mut md = String::new();
+        let ranks = self.ranks();
+
+        let fastest = self
+            .timed()
+            .map(|(_, m)| m.wall.mean)
+            .fold(std::f64::INFINITY, f64::min);
+        if fastest.is_finite() {
+            let (unit, scale) = if fastest < 1.0 {
+                ("ms", 1000.0)
+            } else {
+                ("s", 1.0)
+            };
+            let any_compiled = self.timed().any(|(r, _)| r.compile.is_some());
+            let any_adaptive = self.timed().any(|(_, m)| m.stopped.is_some());
+
+            md += &format!(
+                "| Command | Mean [{0}] | {1:.0}% CI [{0}] | Min [{0}] | Max [{0}] | Relative | Rank | p vs. faster | Memory [MiB] |",
+                unit,
+                CONFIDENCE * 100.0
+            );
+            if any_compiled {
+                md += &format!(" Compile [{0}] | Compile + Run [{0}] |", unit);
+            }
+            if any_adaptive {
+                md += " Runs | Precision |";
+            }
+            md += "\n|:---|---:|---:|---:|---:|---:|---:|---:|---:|";
+            if any_compiled {
+                md += "---:|---:|";
+            }
+            if any_adaptive {
+                md += "---:|---:|";
+            }
+            md += "\n";
+
+            for (r, m) in self.timed() {
+                let memory = match m.peak_rss() {
+                    Some(rss) => format!("{:.1}", rss as f64 / (1024.0 * 1024.0)),
+                    None => String::from("-"),
+                };
+                let (ci_low, ci_high) = bootstrap_mean_ci(&m.times, CONFIDENCE);
+                let rank = &ranks[r.implementation.as_str()];
+                md += &format!(
+                    "| {} | {:.3} ± {:.3} | {:.3} – {:.3} | {:.3} | {:.3} | {:.2} | {} | {} | {} |",
+                    r.link(),
+                    m.wall.mean * scale,
+                    m.wall.stddev * scale,
+                    ci_low * scale,
+                    ci_high * scale,
+                    m.wall.min * scale,
+                    m.wall.max * scale,
+                    m.wall.mean / fastest,
+                    rank.label(),
+                    rank.describe_p_value(),
+                    memory,
+                );
+                if any_compiled {
+                    let compile = match &r.compile {
+                        Some(c) => {
+                            format!("{:.3} ± {:.3}", c.wall.mean * scale, c.wall.stddev * scale)
+                        }
+                        None => String::from("-"),
+                    };
+                    md += &format!(" {} | {:.3} |", compile, r.total_time().unwrap() * scale);
+                }
+                if any_adaptive {
+                    let precision = match m.stopped {
+                        Some(StopReason::Converged) => format!("± {:.2}%", m.mean_cv() * 100.0),
+                        Some(stopped) => {
+                            format!("± {:.2}% ({})", m.mean_cv() * 100.0, stopped.as_str())
+                        }
+                        None => String::from("-"),
+                    };
+                    md += &format!(" {} | {} |", m.times.len(), precision);
+                }
+                md += "\n";
+            }
+            md += &format!(
+                "\nThe confidence interval of the mean is bootstrapped. Ranks are by mean time, and `=` marks implementations a Mann-Whitney U test can't tell apart from their neighbour at p < {0}. p is that test against the next faster implementation. Where there are too few runs for it to ever reach {0}, no tie is marked.\n",
+                SIGNIFICANCE
+            );
+        }
+
+        let failed = self
+            .implementations
+            .iter()
+            .filter(|r| r.status != Status::Ok)
+            .map(|r| {
+                format!(
+                    "| {} | {} | {} |",
+                    r.link(),
+                    r.status.label(),
+                    r.reason.as_ref().map_or("", |s| s.as_str())
+                )
+            })
+            .join("\n");
+        if !failed.is_empty() {
+            md += &format!(
+                "\n## Failed\n\n| Command | Status | Reason |\n|:---|:---|:---|\n{}\n",
+                failed
+            );
+        }
+
+        md
+    }
+}
+
+/// An implementation that failed one of the stages
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageFailure {
+    pub implementation: String,
+    #[serde(default)]
+    pub website: String,
+    /// requirements, fetch, build or prepare
+    pub stage: String,
+    /// Benchmark being prepared, for the prepare stage
+    pub benchmark: Option<String>,
+    /// Where the full error log was saved
+    pub log: String,
+}
+
+impl StageFailure {
+    /// Saves the error log under `results/errors` and records the failure.
+    pub fn record(b: &dyn BFImpl, stage: &str, benchmark: Option<&str>, log: &str) -> Self {
+        let slug = slug(&b.name());
+        let log_path = match benchmark {
+            Some(bench) => format!("results/errors/{}-{}-{}.log", slug, stage, bench),
+            None => format!("results/errors/{}-{}.log", slug, stage),
+        };
+
+        let saved = create_dir_all("results/errors").and_then(|_| std::fs::write(&log_path, log));
+        if let Err(e) = saved {
+            println!("Failed to save error log {}: {}", log_path, e);
+        }
+
+        StageFailure {
+            implementation: b.name(),
+            website: b.website(),
+            stage: stage.to_string(),
+            benchmark: benchmark.map(String::from),
+            log: log_path,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.benchmark {
+            Some(bench) => format!("{} on {}", self.stage, bench),
+            None => self.stage.clone(),
+        }
+    }
+}
+
+/// How one implementation did across all benchmarks
+#[derive(Clone, Debug)]
+pub struct SummaryRow {
+    pub implementation: String,
+    pub website: String,
+    /// Mean time relative to the fastest implementation, per benchmark. None if it wasn't timed.
+    pub slowdowns: Vec<Option<f64>>,
+    /// Status per benchmark. None if it didn't run it.
+    pub statuses: Vec<Option<Status>>,
+    /// Geometric mean of the slowdowns over the benchmarks it was timed on
+    pub geometric_mean: Option<f64>,
+    /// 1 for the lowest geometric mean. Only implementations timed on every benchmark are ranked.
+    pub rank: Option<usize>,
+    /// Benchmarks it crashed on or printed the wrong output for
+    pub failures: usize,
+    pub timeouts: usize,
+}
+
+impl SummaryRow {
+    /// The row of the implementation, added empty if it isn't there yet
+    fn find_or_add<'a>(
+        rows: &'a mut Vec<SummaryRow>,
+        implementation: &str,
+        website: &str,
+        benchmarks: usize,
+    ) -> &'a mut SummaryRow {
+        match rows
+            .iter()
+            .position(|row| row.implementation == implementation)
+        {
+            Some(position) => &mut rows[position],
+            None => {
+                rows.push(SummaryRow {
+                    implementation: implementation.to_string(),
+                    website: website.to_string(),
+                    slowdowns: vec![None; benchmarks],
+                    statuses: vec![None; benchmarks],
+                    geometric_mean: None,
+                    rank: None,
+                    failures: 0,
+                    timeouts: 0,
+                });
+                rows.last_mut().unwrap()
+            }
+        }
+    }
+}
+
+/// Everything measured in one run of the benchmark
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Results {
+    pub benchmarks: Vec<BenchmarkResults>,
+    /// Implementations that failed to fetch, build or prepare
+    #[serde(default)]
+    pub stage_failures: Vec<StageFailure>,
+    /// Upstream revision of each implementation that was measured
+    #[serde(default)]
+    pub revisions: BTreeMap<String, String>,
+}
+
+impl Results {
+    /// Stage failures that kept an implementation from running any benchmark. Failed preparations
+    /// are left out, as they are already in the benchmark's results.
+    pub fn unrun_stage_failures(&self) -> impl Iterator<Item = &StageFailure> {
+        self.stage_failures.iter().filter(|f| f.stage != "prepare")
+    }
+
+    /// One row per implementation, ordered by rank. Unranked implementations come last, by
+    /// geometric mean.
+    pub fn summary(&self) -> Vec<SummaryRow> {
+        let mut rows: Vec<SummaryRow> = Vec::new();
+        for (i, bench) in self.benchmarks.iter().enumerate() {
+            let fastest = bench
+                .timed()
+                .map(|(_, m)| m.wall.mean)
+                .fold(std::f64::INFINITY, f64::min);
+            for r in &bench.implementations {
+                let row = SummaryRow::find_or_add(
+                    &mut rows,
+                    &r.implementation,
+                    &r.website,
+                    self.benchmarks.len(),
+                );
+                row.slowdowns[i] = r.measurement.as_ref().map(|m| m.wall.mean / fastest);
+                row.statuses[i] = Some(r.status);
+                match r.status {
+                    Status::Ok => {}
+                    Status::Timeout => row.timeouts += 1,
+                    Status::Failed | Status::WrongOutput => row.failures += 1,
+                }
+            }
+        }
+
+        // Implementations that failed before running anything failed every benchmark
+        for f in self.unrun_stage_failures() {
+            let row = SummaryRow::find_or_add(
+                &mut rows,
+                &f.implementation,
+                &f.website,
+                self.benchmarks.len(),
+            );
+            for status in row.statuses.iter_mut().filter(|s| s.is_none()) {
+                *status = Some(Status::Failed);
+                row.failures += 1;
+            }
+        }
+
+        for row in &mut rows {
+            let logs: Vec<f64> = row
+                .slowdowns
+                .iter()
+                .filter_map(|s| *s)
+                .map(f64::ln)
+                .collect();
+            if !logs.is_empty() {
+                row.geometric_mean = Some((logs.iter().sum::<f64>() / logs.len() as f64).exp());
+            }
+        }
+        // Ranking a mean over fewer benchmarks against the others would reward skipping the slow
+        // ones, so only implementations timed on all of them are ranked
+        let complete = |row: &SummaryRow| row.slowdowns.iter().all(Option::is_some);
+        rows.sort_by(|a, b| {
+            complete(b)
+                .cmp(&complete(a))
+                .then_with(|| match (a.geometric_mean, b.geometric_mean) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y).unwrap(),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => a.implementation.cmp(&b.implementation),
+                })
+        });
+        for (i, row) in rows.iter_mut().enumerate() {
+            if complete(row) && row.geometric_mean.is_some() {
+                row.rank = Some(i + 1);
+            }
+        }
+        rows
+    }
+
+    /// Matrix of slowdowns against the fastest implementation of each benchmark, ranked by their
+    /// geometric mean
+    fn render_summary_markdown(&self) -> String {
+        let rows = self.summary();
+        if rows.is_empty() {
+            return String::new();
+        }
+
+        let mut md = String::from("# Summary\n| Rank | Implementation | Geometric mean |");
+        for b in &self.benchmarks {
+            md += &format!(
+                " {} |",
+                Path::new(&b.benchmark)
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+            );
+        }
+        md += " Failures | Timeouts |\n|---:|:---|---:|";
+        md += &"---:|".repeat(self.benchmarks.len());
+        md += "---:|---:|\n";
+
+        for row in &rows {
+            md += &format!(
+                "| {} | [`{}`]({}) | {} |",
+                row.rank
+                    .map_or_else(|| String::from("-"), |r| r.to_string()),
+                row.implementation,
+                row.website,
+                row.geometric_mean
+                    .map_or_else(|| String::from("-"), |g| format!("{:.2}", g)),
+            );
+            for (slowdown, status) in row.slowdowns.iter().zip(&row.statuses) {
+                let cell = match (slowdown, status) {
+                    (Some(s), _) => format!("{:.2}", s),
+                    (None, Some(status)) => status.label().to_string(),
+                    (None, None) => String::from("-"),
+                };
+                md += &format!(" {} |", cell);
+            }
+            md += &format!(" {} | {} |\n", row.failures, row.timeouts);
+        }
+        md += "\nEach benchmark column is the mean time relative to the fastest implementation on it. Only implementations timed on every benchmark are ranked. The geometric mean of the others only covers the benchmarks they were timed on.\n";
+        md
+    }
+
+    /// Renders the summary, then every benchmark under its own header, followed by the stage
+    /// failures
+    pub fn render_markdown(&self) -> String {
+        let mut md = self.render_summary_markdown();
+        md += &self
+            .benchmarks
+            .iter()
+            .map(|b| format!("# {}\n{}", b.benchmark, b.render_markdown()))
+            .join("");
+
+        md += &self.render_sizes_markdown();
+
+        if !self.revisions.is_empty() {
+            md += "# Revisions\n| Implementation | Revision |\n|:---|:---|\n";
+            for (name, rev) in &self.revisions {
+                md += &format!("| `{}` | `{}` |\n", name, rev);
+            }
+        }
+
+        if !self.stage_failures.is_empty() {
+            md += "# Stage failures\n| Implementation | Stage | Log |\n|:---|:---|:---|\n";
+            for f in &self.stage_failures {
+                md += &format!(
+                    "| `{}` | {} | `{}` |\n",
+                    f.implementation,
+                    f.describe(),
+                    f.log
+                );
+            }
+        }
+
+        md
+    }
+
+    /// Table of executable sizes produced by compilers, if there are any
+    fn render_sizes_markdown(&self) -> String {
+        let kib = |bytes: Option<u64>| match bytes {
+            Some(b) => format!("{:.1}", b as f64 / 1024.0),
+            None => String::from("-"),
+        };
+
+        let rows = self
+            .benchmarks
+            .iter()
+            .flat_map(|b| {
+                b.implementations
+                    .iter()
+                    .filter_map(move |r| r.size.as_ref().map(|s| (b, r, s)))
+            })
+            .map(|(b, r, s)| {
+                format!(
+                    "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+                    b.benchmark,
+                    r.link(),
+                    kib(Some(s.size)),
+                    kib(s.stripped),
+                    kib(s.section(".text")),
+                    kib(s.section(".rodata")),
+                    kib(s.section(".data")),
+                    kib(s.section(".bss")),
+                    kib(s.generated_source),
+                )
+            })
+            .join("\n");
+
+        if rows.is_empty() {
+            return String::new();
+        }
+        format!(
+            "# Executable sizes\n| Benchmark | Compiler | Size [KiB] | Stripped [KiB] | .text [KiB] | .rodata [KiB] | .data [KiB] | .bss [KiB] | Generated source [KiB] |\n|:---|:---|---:|---:|---:|---:|---:|---:|---:|\n{}\n",
+            rows
+        )
+    }
+
+    /// Prints which implementations failed and at which stage
+    pub fn print_stage_failures(&self) {
+        if self.stage_failures.is_empty() {
+            return;
+        }
+
+        println!("==========================================");
+        println!("Failed implementations:");
+        for f in &self.stage_failures {
+            println!(
+                "  {}: {} (log at {})",
+                f.implementation,
+                f.describe(),
+                f.log
+            );
+        }
+    }
+
+    /// One row per benchmark and implementation. Samples are space separated.
+    pub fn write_csv(&self, path: &str) -> Result<(), String> {
+        let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+        writer
+            .write_record(&[
+                "benchmark",
+                "implementation",
+                "interpreted",
+                "status",
+                "reason",
+                "runs",
+                "mean",
+                "stddev",
+                "median",
+                "min",
+                "max",
+                "user",
+                "system",
+                "max_rss",
+                "samples",
+                "compile_mean",
+                "compile_stddev",
+                "compile_samples",
+                "total",
+                "exe_size",
+                "exe_stripped_size",
+                "generated_source_size",
+                "mean_ci_low",
+                "mean_ci_high",
+                "rank",
+                "p_vs_faster",
+                "mean_cv",
+                "stopped",
+            ])
+            .map_err(|e| e.to_string())?;
+
+        for bench in &self.benchmarks {
+            let ranks = bench.ranks();
+            for r in &bench.implementations {
+                let mut record = vec![
+                    bench.benchmark.clone(),
+                    r.implementation.clone(),
+                    r.interpreted.to_string(),
+                    r.status.as_str().to_string(),
+                    r.reason.clone().unwrap_or_default(),
+                ];
+                match &r.measurement {
+                    Some(m) => record.extend(vec![
+                        m.times.len().to_string(),
+                        m.wall.mean.to_string(),
+                        m.wall.stddev.to_string(),
+                        m.wall.median.to_string(),
+                        m.wall.min.to_string(),
+                        m.wall.max.to_string(),
+                        m.user.to_string(),
+                        m.system.to_string(),
+                        m.peak_rss().map(|r| r.to_string()).unwrap_or_default(),
+                        m.times.iter().map(|t| t.to_string()).join(" "),
+                    ]),
+                    None => record.extend(vec![String::new(); 10]),
+                }
+                match &r.compile {
+                    Some(c) => record.extend(vec![
+                        c.wall.mean.to_string(),
+                        c.wall.stddev.to_string(),
+                        c.times.iter().map(|t| t.to_string()).join(" "),
+                    ]),
+                    None => record.extend(vec![String::new(); 3]),
+                }
+                record.push(r.total_time().map(|t| t.to_string()).unwrap_or_default());
+                match &r.size {
+                    Some(s) => record.extend(vec![
+                        s.size.to_string(),
+                        s.stripped.map(|v| v.to_string()).unwrap_or_default(),
+                        s.generated_source
+                            .map(|v| v.to_string())
+                            .unwrap_or_default(),
+                    ]),
+                    None => record.extend(vec![String::new(); 3]),
+                }
+                match (&r.measurement, ranks.get(r.implementation.as_str())) {
+                    (Some(m), Some(rank)) => {
+                        let (ci_low, ci_high) = bootstrap_mean_ci(&m.times, CONFIDENCE);
+                        record.extend(vec![
+                            ci_low.to_string(),
+                            ci_high.to_string(),
+                            rank.label(),
+                            rank.p_value.map(|p| p.to_string()).unwrap_or_default(),
+                        ]);
+                    }
+                    _ => record.extend(vec![String::new(); 4]),
+                }
+                match &r.measurement {
+                    Some(m) => record.extend(vec![
+                        m.mean_cv().to_string(),
+                        m.stopped.map(|s| s.as_str()).unwrap_or("").to_string(),
+                    ]),
+                    None => record.extend(vec![String::new(); 2]),
+                }
+                writer.write_record(&record).map_err(|e| e.to_string())?;
+            }
+        }
+
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        serde_json::to_writer_pretty(file, self).map_err(|e| e.to_string())
+    }
+
+    pub fn read_json(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_reader(file).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Replaces everything known about the given implementations with the newer results, keeping
+    /// what is known about the others.
+    pub fn merge(&mut self, newer: Results, implementations: &[String]) {
+        let replaced = |name: &String| implementations.contains(name);
+
+        for bench in newer.benchmarks {
+            match self
+                .benchmarks
+                .iter_mut()
+                .find(|b| b.benchmark == bench.benchmark)
+            {
+                Some(existing) => {
+                    existing
+                        .implementations
+                        .retain(|r| !replaced(&r.implementation));
+                    existing.implementations.extend(bench.implementations);
+                    existing
+                        .implementations
+                        .sort_by(|a, b| a.implementation.cmp(&b.implementation));
+                }
+                None => self.benchmarks.push(bench),
+            }
+        }
+        self.benchmarks
+            .sort_by(|a, b| a.benchmark.cmp(&b.benchmark));
+
+        self.stage_failures.retain(|f| !replaced(&f.implementation));
+        self.stage_failures.extend(newer.stage_failures);
+
+        self.revisions.retain(|name, _| !replaced(name));
+        self.revisions.extend(newer.revisions);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Size of what a compiler produced for one benchmark. All sizes are in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExeSize {
+    /// Size of the executable on disk
+    pub size: u64,
+    /// Size after running `strip`, if it's available
+    pub stripped: Option<u64>,
+    /// Size of each section as reported by `size -A`
+    pub sections: BTreeMap<String, u64>,
+    /// Total size of intermediate sources, such as generated C
+    pub generated_source: Option<u64>,
+}
+
+impl ExeSize {
+    pub fn section(&self, name: &str) -> Option<u64> {
+        self.sections.get(name).cloned()
+    }
+}
+
+fn file_size(path: &Path) -> Result<u64, String> {
+    std::fs::metadata(path)
+        .map(|m| m.len())
+        .map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+}
+
+fn stripped_size(exe: &Path) -> Option<u64> {
+    let stripped = exe.with_extension("stripped");
+    let stripped_str = stripped.to_string_lossy().to_string();
+    let exe_str = exe.to_string_lossy().to_string();
+
+    let size = run_command(Command::new("strip").args(&["-o", &stripped_str, &exe_str]))
+        .ok()
+        .and_then(|_| file_size(&stripped).ok());
+    let _ = std::fs::remove_file(&stripped);
+    size
+}
+
+fn section_sizes(exe: &Path) -> BTreeMap<String, u64> {
+    let exe_str = exe.to_string_lossy().to_string();
+    let output = match run_command_output(Command::new("size").args(&["-A", &exe_str])) {
+        Ok(o) => o,
+        Err(_) => return BTreeMap::new(),
+    };
+
+    // Lines look like `.text  12345  4096`, with a header and a total we skip
+    String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let size = parts.next()?.parse().ok()?;
+            if name.starts_with('.') {
+                Some((name.to_string(), size))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Measures the executable and generated sources a compiler produced in its prepare stage.
+pub fn measure_size(b: &dyn BFImpl) -> Result<Option<ExeSize>, String> {
+    let exe = match b.compiled_exe() {
+        Some(exe) => exe,
+        None => return Ok(None),
+    };
+
+    let sources = b.generated_sources();
+    let generated_source = if sources.is_empty() {
+        None
+    } else {
+        let mut total = 0;
+        for s in &sources {
+            total += file_size(s)?;
+        }
+        Some(total)
+    };
+
+    Ok(Some(ExeSize {
+        size: file_size(&exe)?,
+        stripped: if windows() { None } else { stripped_size(&exe) },
+        sections: if windows() {
+            BTreeMap::new()
+        } else {
+            section_sizes(&exe)
+        },
+        generated_source,
+    }))
+}
@@ -0,0 +1,128 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Where the state is kept between invocations
+const STATE_PATH: &str = "build/state.json";
+/// Where the results of every `run` are merged into, for `report`
+pub const SAVED_RESULTS_PATH: &str = "build/results.json";
+
+/// What the fetch and build stages got done, so later subcommands can pick up from there.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// Implementations that were fetched successfully
+    pub fetched: BTreeSet<String>,
+    /// Implementations that were built successfully, with what from in `stamps`
+    pub built: BTreeSet<String>,
+    /// Latest failure of the requirements, fetch and build stages of each implementation
+    pub failures: Vec<StageFailure>,
+    /// What each built implementation was built from
+    #[serde(default)]
+    pub stamps: BTreeMap<String, BuildStamp>,
+}
+
+/// Everything a build depends on. A build can be reused as long as its stamp stays the same.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuildStamp {
+    /// Revision of the source, None if it isn't known
+    pub revision: Option<String>,
+    /// SHA-256 of the build recipe
+    pub recipe: String,
+    /// Version of every tool the implementation needs
+    pub tools: BTreeMap<String, String>,
+    /// Versions of the C and C++ compilers, for CMake builds
+    #[serde(default)]
+    pub compilers: BTreeMap<String, String>,
+}
+
+impl BuildStamp {
+    pub fn new(b: &dyn BFImpl, toolbox: &mut Toolbox) -> Self {
+        use sha2::{Digest, Sha256};
+
+        let requirements = b.requirements();
+        BuildStamp {
+            revision: b.revision(),
+            recipe: format!("{:x}", Sha256::digest(b.build_recipe().as_bytes())),
+            tools: requirements
+                .iter()
+                .map(|r| (r.tool.clone(), toolbox.describe(&r.tool)))
+                .collect(),
+            compilers: compiler_versions(&requirements),
+        }
+    }
+
+    /// If a build with the old stamp is still up to date. Sources without a known revision or with
+    /// uncommitted changes are always rebuilt, as the revision doesn't change with further edits.
+    pub fn matches(&self, old: Option<&BuildStamp>) -> bool {
+        let committed = self
+            .revision
+            .as_ref()
+            .map_or(false, |r| !r.ends_with("-dirty"));
+        committed && old == Some(self)
+    }
+}
+
+impl State {
+    /// Loads the state, or an empty one if there is none yet or it can't be read.
+    pub fn load() -> Self {
+        match read_to_string(STATE_PATH) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                println!("Ignoring unreadable {}: {}", STATE_PATH, e);
+                State::default()
+            }),
+            Err(_) => State::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let result = File::create(STATE_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|f| serde_json::to_writer_pretty(f, self).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("Failed to save {}: {}", STATE_PATH, e);
+        }
+    }
+
+    /// Records that the stage succeeded, clearing its last failure.
+    pub fn succeeded(&mut self, implementation: &str, stage: &str) {
+        self.failures
+            .retain(|f| !(f.implementation == implementation && f.stage == stage));
+        match stage {
+            "fetch" => {
+                self.fetched.insert(implementation.to_string());
+            }
+            "build" => {
+                self.built.insert(implementation.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Records that the stage failed, replacing its last failure.
+    pub fn failed(&mut self, failure: StageFailure) {
+        match failure.stage.as_str() {
+            "fetch" => {
+                self.fetched.remove(&failure.implementation);
+                self.built.remove(&failure.implementation);
+            }
+            "build" => {
+                self.built.remove(&failure.implementation);
+            }
+            _ => {}
+        }
+        self.failures
+            .retain(|f| !(f.implementation == failure.implementation && f.stage == failure.stage));
+        self.failures.push(failure);
+    }
+
+    /// Short summary of how far the implementation got, for `list`
+    pub fn describe(&self, implementation: &str) -> &'static str {
+        if self.built.contains(implementation) {
+            "built"
+        } else if self.fetched.contains(implementation) {
+            "fetched"
+        } else {
+            "-"
+        }
+    }
+}
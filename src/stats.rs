@@ -0,0 +1,285 @@
+use crate::*;
+
+/// Two-sided p-value of Welch's t-test for the two samples having the same mean. None if either
+/// sample has fewer than two values.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (sa, sb) = (Statistics::from_values(a), Statistics::from_values(b));
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let va = sa.stddev.powi(2) / na;
+    let vb = sb.stddev.powi(2) / nb;
+    if va + vb == 0.0 {
+        return Some(if sa.mean == sb.mean { 1.0 } else { 0.0 });
+    }
+
+    let t = (sa.mean - sb.mean) / (va + vb).sqrt();
+    let df = (va + vb).powi(2) / (va.powi(2) / (na - 1.0) + vb.powi(2) / (nb - 1.0));
+    Some(incomplete_beta(df / 2.0, 0.5, df / (df + t * t)))
+}
+
+/// Two-sided p-value of the Mann-Whitney U test for the two samples coming from the same
+/// distribution. Exact for small samples without ties, a normal approximation otherwise. None if
+/// either sample is empty.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n, m) = (a.len(), b.len());
+
+    let mut all: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&v| (v, true))
+        .chain(b.iter().map(|&v| (v, false)))
+        .collect();
+    all.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+
+    // Ties get the average of the ranks they span
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let j = (i..all.len())
+            .find(|&j| all[j].0 != all[i].0)
+            .unwrap_or(all.len());
+        let rank = (i + j + 1) as f64 / 2.0;
+        rank_sum_a += rank * all[i..j].iter().filter(|v| v.1).count() as f64;
+        let t = (j - i) as f64;
+        tie_term += t.powi(3) - t;
+        i = j;
+    }
+
+    let (nf, mf) = (n as f64, m as f64);
+    let u_a = rank_sum_a - nf * (nf + 1.0) / 2.0;
+    let u = u_a.min(nf * mf - u_a);
+
+    if tie_term == 0.0 && n <= 20 && m <= 20 {
+        let counts = u_distribution(n, m);
+        let total: f64 = counts.iter().sum();
+        let at_most: f64 = counts[..=u as usize].iter().sum();
+        return Some((2.0 * at_most / total).min(1.0));
+    }
+
+    let total = nf + mf;
+    let sigma = (nf * mf / 12.0 * ((total + 1.0) - tie_term / (total * (total - 1.0)))).sqrt();
+    if sigma == 0.0 {
+        return Some(1.0);
+    }
+    let z = (((u_a - nf * mf / 2.0).abs() - 0.5) / sigma).max(0.0);
+    Some(erfc(z / std::f64::consts::SQRT_2).min(1.0))
+}
+
+/// Smallest two-sided p-value the Mann-Whitney U test can give for samples of n and m values,
+/// reached when they don't overlap at all
+pub fn mann_whitney_min_p(n: usize, m: usize) -> f64 {
+    // One of the two most extreme of the n + m choose n orderings
+    let orderings = (1..=n).fold(1.0, |c, k| c * (m + k) as f64 / k as f64);
+    (2.0 / orderings).min(1.0)
+}
+
+/// How many orderings of n and m values give each U statistic
+fn u_distribution(n: usize, m: usize) -> Vec<f64> {
+    // counts[i][j] is the distribution for i and j values
+    let mut counts = vec![vec![vec![1.0]; m + 1]; n + 1];
+    for i in 1..=n {
+        for j in 1..=m {
+            let mut dist = vec![0.0; i * j + 1];
+            // The largest value is either one of the i, beating all j, or one of the j
+            for (u, c) in counts[i - 1][j].iter().enumerate() {
+                dist[u + j] += c;
+            }
+            for (u, c) in counts[i][j - 1].iter().enumerate() {
+                dist[u] += c;
+            }
+            counts[i][j] = dist;
+        }
+    }
+    counts[n][m].clone()
+}
+
+/// Percentile bootstrap confidence interval of the mean, at the given confidence level
+pub fn bootstrap_mean_ci(values: &[f64], confidence: f64) -> (f64, f64) {
+    const RESAMPLES: usize = 2000;
+    assert!(!values.is_empty());
+
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let mut means: Vec<f64> = (0..RESAMPLES)
+        .map(|_| {
+            (0..values.len())
+                .map(|_| values[rng.below(values.len())])
+                .sum::<f64>()
+                / values.len() as f64
+        })
+        .collect();
+    means.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let tail = (1.0 - confidence) / 2.0;
+    let low = (tail * RESAMPLES as f64).floor() as usize;
+    let high = ((1.0 - tail) * RESAMPLES as f64).ceil() as usize - 1;
+    (means[low], means[high.min(RESAMPLES - 1)])
+}
+
+/// Small fixed-seed generator, so the same samples always give the same report
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+/// Complementary error function, with a fractional error below 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, c| c + t * acc);
+    let result = t * (-z * z + polynomial).exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+/// Stars for how significant a p-value is, empty if it isn't
+pub fn significance_marker(p: Option<f64>) -> &'static str {
+    match p {
+        Some(p) if p < 0.001 => "***",
+        Some(p) if p < 0.01 => "**",
+        Some(p) if p < 0.05 => "*",
+        _ => "",
+    }
+}
+
+/// Natural logarithm of the gamma function, using the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut y = x;
+    let mut series = 1.000_000_000_190_015;
+    for c in &COEFFICIENTS {
+        y += 1.0;
+        series += c / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly on this side only
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, evaluated with Lentz's method
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-30;
+    let clamp = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..300 {
+        let m = f64::from(m);
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        h *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let step = d * c;
+        h *= step;
+        if (step - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) -> bool {
+        (actual - expected).abs() < tolerance
+    }
+
+    #[test]
+    fn mann_whitney_separated_samples() {
+        let p = mann_whitney_u(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap();
+        assert!(close(p, 0.1, 1e-12), "{}", p);
+        assert!(close(mann_whitney_min_p(3, 3), 0.1, 1e-12));
+
+        let p = mann_whitney_u(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]).unwrap();
+        assert!(close(p, 2.0 / 252.0, 1e-12), "{}", p);
+        assert!(close(mann_whitney_min_p(5, 5), 2.0 / 252.0, 1e-12));
+    }
+
+    #[test]
+    fn mann_whitney_overlapping_samples() {
+        let p = mann_whitney_u(&[1.0, 3.0, 5.0], &[2.0, 4.0, 6.0]).unwrap();
+        assert!(close(p, 0.7, 1e-12), "{}", p);
+        assert_eq!(mann_whitney_u(&[], &[1.0]), None);
+    }
+
+    #[test]
+    fn welch_matches_reference() {
+        // t = 3.674 with 4 degrees of freedom
+        let p = welch_t_test(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap();
+        assert!(close(p, 0.021_312, 1e-5), "{}", p);
+        // t = 2.251 with 5.52 degrees of freedom
+        let p = welch_t_test(&[1.0, 2.0, 3.0, 4.0], &[2.0, 4.0, 6.0, 8.0, 10.0]).unwrap();
+        assert!(close(p, 0.069_133, 1e-5), "{}", p);
+        assert_eq!(welch_t_test(&[1.0], &[2.0, 3.0]), None);
+    }
+
+    #[test]
+    fn confidence_interval_contains_mean() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let (low, high) = bootstrap_mean_ci(&values, 0.95);
+        assert!(low < 3.0 && 3.0 < high, "{} {}", low, high);
+        assert!(low >= 1.0 && high <= 5.0);
+
+        let (low, high) = bootstrap_mean_ci(&[2.0, 2.0, 2.0], 0.95);
+        assert_eq!((low, high), (2.0, 2.0));
+    }
+}
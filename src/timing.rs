@@ -0,0 +1,577 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Which program takes the measurements
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimingBackend {
+    /// Built-in measurement loop
+    Native,
+    /// External hyperfine binary
+    Hyperfine,
+}
+
+impl FromStr for TimingBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(TimingBackend::Native),
+            "hyperfine" => Ok(TimingBackend::Hyperfine),
+            _ => Err(format!("unknown timing backend {}", s)),
+        }
+    }
+}
+
+/// How many times to run each command
+#[derive(Copy, Clone, Debug)]
+pub struct TimingOptions {
+    /// Untimed runs before measuring
+    pub warmup: usize,
+    /// Timed runs
+    pub runs: usize,
+    /// Longest a single run may take before it is killed
+    pub timeout: Option<Duration>,
+    /// Keep sampling past `runs` until the measurement is precise enough
+    pub adaptive: Option<AdaptiveSampling>,
+}
+
+/// When adaptive sampling stops taking more runs
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveSampling {
+    /// Coefficient of variation of the mean to reach, as a fraction
+    pub target_cv: f64,
+    /// Most time to spend on the timed runs of one command
+    pub budget: Duration,
+    /// Most timed runs of one command
+    pub max_runs: usize,
+}
+
+/// Parses `--target-cv`, which can only be reached if it's positive
+pub fn parse_target_cv(s: &str) -> Result<f64, String> {
+    let percent: f64 = s
+        .parse()
+        .map_err(|_| format!("invalid target CV {}, expected a percentage", s))?;
+    if !percent.is_finite() || percent <= 0.0 {
+        return Err(format!("target CV must be positive, got {}", percent));
+    }
+    Ok(percent)
+}
+
+/// Parses `--time-budget`
+pub fn parse_time_budget(s: &str) -> Result<f64, String> {
+    let seconds: f64 = s
+        .parse()
+        .map_err(|_| format!("invalid time budget {}, expected SECONDS", s))?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("time budget must be positive, got {}", seconds));
+    }
+    Ok(seconds)
+}
+
+/// Why adaptive sampling stopped
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The target coefficient of variation was reached
+    Converged,
+    /// The time budget ran out first
+    Budget,
+    /// The run cap was hit first
+    Cap,
+}
+
+impl StopReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StopReason::Converged => "converged",
+            StopReason::Budget => "budget",
+            StopReason::Cap => "cap",
+        }
+    }
+}
+
+/// Summary statistics over a set of values
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Statistics {
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+}
+
+impl Statistics {
+    pub fn from_values(values: &[f64]) -> Self {
+        assert!(!values.is_empty());
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let stddev = if values.len() > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let mid = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 0 {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+
+        Statistics {
+            mean,
+            stddev,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            median,
+        }
+    }
+}
+
+/// Timing results of a single command. All times are in seconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Measurement {
+    /// Command as given by the implementation, without stdin redirection
+    pub command: String,
+    /// Wall time of every timed run
+    pub times: Vec<f64>,
+    /// Statistics over `times`
+    pub wall: Statistics,
+    /// Mean user time
+    pub user: f64,
+    /// Mean system time
+    pub system: f64,
+    /// Peak resident set size of every timed run in bytes. Empty if the backend can't measure it.
+    #[serde(default)]
+    pub max_rss: Vec<u64>,
+    /// Why sampling stopped, if it was adaptive
+    #[serde(default)]
+    pub stopped: Option<StopReason>,
+}
+
+impl Measurement {
+    fn new(command: String, times: Vec<f64>, user: f64, system: f64, max_rss: Vec<u64>) -> Self {
+        let wall = Statistics::from_values(&times);
+        Measurement {
+            command,
+            times,
+            wall,
+            user,
+            system,
+            max_rss,
+            stopped: None,
+        }
+    }
+
+    /// Coefficient of variation of the mean: the standard error relative to the mean
+    pub fn mean_cv(&self) -> f64 {
+        mean_cv(&self.times)
+    }
+
+    /// Highest peak resident set size over all runs in bytes
+    pub fn peak_rss(&self) -> Option<u64> {
+        self.max_rss.iter().cloned().max()
+    }
+
+    /// Human readable summary in the spirit of hyperfine's terminal output
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "  Time (mean ± σ):     {:.4} s ± {:.4} s    [User: {:.4} s, System: {:.4} s]\n  Range (min … median … max):   {:.4} s … {:.4} s … {:.4} s    {} runs",
+            self.wall.mean,
+            self.wall.stddev,
+            self.user,
+            self.system,
+            self.wall.min,
+            self.wall.median,
+            self.wall.max,
+            self.times.len(),
+        );
+        if let Some(rss) = self.peak_rss() {
+            summary += &format!("\n  Peak memory:         {}", format_bytes(rss));
+        }
+        if let Some(stopped) = self.stopped {
+            summary += &format!(
+                "\n  Precision:           ± {:.2}% of the mean, {}",
+                self.mean_cv() * 100.0,
+                stopped.as_str()
+            );
+        }
+        summary
+    }
+}
+
+fn mean_cv(times: &[f64]) -> f64 {
+    if times.len() < 2 {
+        return std::f64::INFINITY;
+    }
+    let stats = Statistics::from_values(times);
+    stats.stddev / (times.len() as f64).sqrt() / stats.mean
+}
+
+/// Time spent in the prepare stage of a compiler. All times are in seconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompileTiming {
+    /// Wall time of every prepare run
+    pub times: Vec<f64>,
+    /// Statistics over `times`
+    pub wall: Statistics,
+}
+
+/// Runs the prepare stage, timing it `runs` times for compilers. Interpreters are prepared once
+/// and not timed.
+pub fn measure_prepare(
+    b: &dyn BFImpl,
+    file: &Path,
+    runs: usize,
+) -> Result<Option<CompileTiming>, String> {
+    if b.interpreted() {
+        b.prepare(file.to_path_buf())?;
+        return Ok(None);
+    }
+
+    let runs = runs.max(1);
+    let mut times = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        b.prepare(file.to_path_buf())?;
+        times.push(start.elapsed().as_secs_f64());
+    }
+
+    let wall = Statistics::from_values(&times);
+    Ok(Some(CompileTiming { times, wall }))
+}
+
+/// Formats a byte count with a binary unit
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, units[unit])
+}
+
+/// Resource usage of a single run
+#[derive(Clone, Debug, Default)]
+struct RunUsage {
+    wall: f64,
+    user: f64,
+    system: f64,
+    /// Peak resident set size in bytes, if known
+    max_rss: Option<u64>,
+}
+
+/// Shortest wall time a run is recorded as after taking off the shell startup, so a run faster
+/// than the startup's noise never takes zero time and makes slowdowns divide by zero
+const TIMER_RESOLUTION: f64 = 1e-6;
+
+impl RunUsage {
+    /// Takes off the cost of starting the shell the command ran in
+    fn without(self, overhead: &RunUsage) -> Self {
+        RunUsage {
+            wall: (self.wall - overhead.wall).max(TIMER_RESOLUTION),
+            user: (self.user - overhead.user).max(0.0),
+            system: (self.system - overhead.system).max(0.0),
+            max_rss: self.max_rss,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Mean cost of starting the shell, measured once per process by running an empty command
+    /// through it
+    static ref SHELL_OVERHEAD: RunUsage = shell_overhead();
+}
+
+fn shell_overhead() -> RunUsage {
+    const RUNS: usize = 20;
+    let usages: Vec<RunUsage> = (0..RUNS)
+        .filter_map(|_| run_process(shell_command(""), None, None).ok())
+        .collect();
+    if usages.is_empty() {
+        return RunUsage::default();
+    }
+    let mean = |f: fn(&RunUsage) -> f64| usages.iter().map(f).sum::<f64>() / usages.len() as f64;
+    RunUsage {
+        wall: mean(|u| u.wall),
+        user: mean(|u| u.user),
+        system: mean(|u| u.system),
+        max_rss: None,
+    }
+}
+
+/// The command as a program and its arguments, if it has nothing the shell would need to
+/// interpret
+fn direct_command(command: &str) -> Option<Command> {
+    const SHELL_SYNTAX: &[char] = &[
+        '|', '&', ';', '<', '>', '(', ')', '$', '`', '\\', '"', '\'', '*', '?', '[', ']', '#', '~',
+        '=', '%', '{', '}', '\n',
+    ];
+    if command.contains(SHELL_SYNTAX) {
+        return None;
+    }
+    let mut words = command.split_whitespace();
+    let mut c = Command::new(words.next()?);
+    c.args(words);
+    Some(c)
+}
+
+/// Runs a command once. Commands without shell syntax are spawned directly, the others go through
+/// the shell and have its startup cost taken off, as hyperfine does.
+fn run_once(
+    command: &str,
+    input: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<RunUsage, Failure> {
+    match direct_command(command) {
+        Some(c) => run_process(c, input, timeout),
+        None => Ok(run_process(shell_command(command), input, timeout)?.without(&SHELL_OVERHEAD)),
+    }
+}
+
+fn stdin_for(input: Option<&Path>) -> Result<Stdio, Failure> {
+    match input {
+        Some(input) => File::open(input).map(Stdio::from).map_err(|e| {
+            Failure::new(
+                Status::Failed,
+                format!("failed to open {}: {}", input.to_string_lossy(), e),
+            )
+        }),
+        None => Ok(Stdio::null()),
+    }
+}
+
+fn timed_out(timeout: Option<Duration>) -> Failure {
+    Failure::new(
+        Status::Timeout,
+        format!("timed out after {:.1} s", timeout.unwrap().as_secs_f64()),
+    )
+}
+
+#[cfg(not(windows))]
+fn run_process(
+    mut c: Command,
+    input: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<RunUsage, Failure> {
+    let failed = |reason| Err(Failure::new(Status::Failed, reason));
+
+    own_process_group(&mut c)
+        .stdin(stdin_for(input)?)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let start = Instant::now();
+    let child = match c.spawn() {
+        Ok(c) => c,
+        Err(e) => return failed(format!("failed to launch: {}", e)),
+    };
+    let pid = child.id() as libc::pid_t;
+    let watchdog = Watchdog::start(child.id(), timeout);
+
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        let ret = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if ret == pid {
+            break;
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            Watchdog::stop(watchdog);
+            return failed(format!("wait4 failed: {}", error));
+        }
+    }
+    let wall = start.elapsed().as_secs_f64();
+
+    if Watchdog::stop(watchdog) {
+        return Err(timed_out(timeout));
+    }
+    if !libc::WIFEXITED(status) {
+        return failed(String::from("killed by signal"));
+    }
+    if libc::WEXITSTATUS(status) != 0 {
+        return failed(format!("exited with status {}", libc::WEXITSTATUS(status)));
+    }
+
+    let timeval = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+    // ru_maxrss is in bytes on macOS and kilobytes everywhere else
+    let rss_scale = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    Ok(RunUsage {
+        wall,
+        user: timeval(usage.ru_utime),
+        system: timeval(usage.ru_stime),
+        max_rss: Some(usage.ru_maxrss as u64 * rss_scale),
+    })
+}
+
+#[cfg(windows)]
+fn run_process(
+    mut c: Command,
+    input: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<RunUsage, Failure> {
+    let failed = |reason| Err(Failure::new(Status::Failed, reason));
+
+    c.stdin(stdin_for(input)?)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let start = Instant::now();
+    let mut child = match c.spawn() {
+        Ok(c) => c,
+        Err(e) => return failed(format!("failed to launch: {}", e)),
+    };
+    let watchdog = Watchdog::start(child.id(), timeout);
+    let status = child.wait();
+    let wall = start.elapsed().as_secs_f64();
+
+    if Watchdog::stop(watchdog) {
+        return Err(timed_out(timeout));
+    }
+    let status = match status {
+        Ok(s) => s,
+        Err(e) => return failed(format!("failed to wait: {}", e)),
+    };
+    if !status.success() {
+        return failed(format!("exited with status {:?}", status.code()));
+    }
+
+    // No wait4 on windows, so only wall time is available
+    Ok(RunUsage {
+        wall,
+        user: 0.0,
+        system: 0.0,
+        max_rss: None,
+    })
+}
+
+/// Times a command with the built-in measurement loop. In adaptive mode `runs` is the minimum
+/// and it keeps sampling until the mean is precise enough or the budget or cap is hit.
+pub fn measure(
+    command: &str,
+    input: Option<&Path>,
+    options: &TimingOptions,
+) -> Result<Measurement, Failure> {
+    let stdin_command = with_input(command, input);
+    let runs = match options.adaptive {
+        // The coefficient of variation needs at least two runs
+        Some(adaptive) => options.runs.max(2).min(adaptive.max_runs.max(2)),
+        None => options.runs.max(1),
+    };
+
+    for _ in 0..options.warmup {
+        run_once(command, input, options.timeout)?;
+    }
+
+    let mut times = Vec::with_capacity(runs);
+    let mut user = 0.0;
+    let mut system = 0.0;
+    let mut max_rss = Vec::with_capacity(runs);
+    let mut stopped = None;
+    let start = Instant::now();
+    loop {
+        let i = times.len();
+        match options.adaptive {
+            Some(_) if i >= runs => print!(
+                "\r  Run {} (± {:.2}%): {}",
+                i + 1,
+                mean_cv(&times) * 100.0,
+                stdin_command
+            ),
+            _ => print!("\r  Run {}/{}: {}", i + 1, runs, stdin_command),
+        }
+        std::io::stdout().flush().unwrap();
+
+        let usage = run_once(command, input, options.timeout)?;
+        times.push(usage.wall);
+        user += usage.user;
+        system += usage.system;
+        max_rss.extend(usage.max_rss);
+
+        if times.len() < runs {
+            continue;
+        }
+        match options.adaptive {
+            None => break,
+            Some(adaptive) => {
+                stopped = if mean_cv(&times) <= adaptive.target_cv {
+                    Some(StopReason::Converged)
+                } else if times.len() >= adaptive.max_runs {
+                    Some(StopReason::Cap)
+                } else if start.elapsed() >= adaptive.budget {
+                    Some(StopReason::Budget)
+                } else {
+                    None
+                };
+                if stopped.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+    println!();
+
+    let count = times.len() as f64;
+    let mut measurement = Measurement::new(
+        command.to_string(),
+        times,
+        user / count,
+        system / count,
+        max_rss,
+    );
+    measurement.stopped = stopped;
+    Ok(measurement)
+}
+
+#[derive(Deserialize)]
+struct HyperfineExport {
+    results: Vec<HyperfineResult>,
+}
+
+#[derive(Deserialize)]
+struct HyperfineResult {
+    user: f64,
+    system: f64,
+    times: Vec<f64>,
+}
+
+/// Times all commands with hyperfine and reads its JSON export back.
+pub fn measure_hyperfine(
+    commands: &[String],
+    input: Option<&Path>,
+    options: &TimingOptions,
+    json_path: &str,
+) -> Result<Vec<Measurement>, String> {
+    let mut args: Vec<String> = commands.iter().map(|c| with_input(c, input)).collect();
+    args.extend(vec![
+        "--show-output".into(),
+        "--warmup".into(),
+        options.warmup.to_string(),
+        "--runs".into(),
+        options.runs.max(1).to_string(),
+        "--export-json".into(),
+        json_path.into(),
+    ]);
+    if windows() {
+        args.extend(vec!["--shell".into(), "powershell".into()]);
+    }
+
+    run_command(Command::new("hyperfine").args(&args))?;
+
+    let contents = read_to_string(json_path).map_err(io_error)?;
+    let export: HyperfineExport = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    Ok(commands
+        .iter()
+        .zip(export.results.into_iter())
+        .map(|(c, r)| Measurement::new(c.clone(), r.times, r.user, r.system, Vec::new()))
+        .collect())
+}
@@ -0,0 +1,309 @@
+use crate::*;
+use serde::{Deserialize, Deserializer};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Dotted version number, compared component by component with missing components as zero
+#[derive(Clone, Debug)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    /// Finds the first version number in the output of `tool --version`.
+    fn find(text: &str) -> Option<Self> {
+        lazy_static::lazy_static! {
+            static ref VERSION: Regex = Regex::new(r"\d+(\.\d+)+").unwrap();
+        }
+        VERSION.find(text).and_then(|m| m.as_str().parse().ok())
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('.')
+            .map(|p| p.parse().map_err(|_| format!("invalid version {}", s)))
+            .collect::<Result<_, _>>()
+            .map(Version)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().join("."))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let get = |v: &Version, i: usize| v.0.get(i).cloned().unwrap_or(0);
+        (0..len)
+            .map(|i| get(self, i).cmp(&get(other, i)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A tool an implementation needs, written as `cmake`, `cmake >= 3.12` or `python >= 2.7, < 3`.
+#[derive(Clone, Debug)]
+pub struct Requirement {
+    pub tool: String,
+    pub min_version: Option<Version>,
+    /// Versions from this one up are too new
+    pub below: Option<Version>,
+}
+
+impl Requirement {
+    pub fn tool(tool: &str) -> Self {
+        Requirement {
+            tool: tool.to_string(),
+            min_version: None,
+            below: None,
+        }
+    }
+}
+
+impl FromStr for Requirement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, char::is_whitespace);
+        let tool = parts.next().unwrap_or("").to_string();
+        if tool.is_empty() {
+            return Err(String::from("empty requirement"));
+        }
+
+        let mut requirement = Requirement::tool(&tool);
+        for constraint in parts.next().unwrap_or("").split(',') {
+            let constraint = constraint.trim();
+            if constraint.starts_with(">=") {
+                requirement.min_version = Some(constraint[2..].trim().parse()?);
+            } else if constraint.starts_with('<') {
+                requirement.below = Some(constraint[1..].trim().parse()?);
+            } else if !constraint.is_empty() {
+                return Err(format!(
+                    "invalid requirement {}, expected TOOL, TOOL >= VERSION or TOOL >= VERSION, < VERSION",
+                    s
+                ));
+            }
+        }
+        Ok(requirement)
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tool)?;
+        if let Some(min) = &self.min_version {
+            write!(f, " >= {}", min)?;
+        }
+        if let Some(below) = &self.below {
+            let separator = if self.min_version.is_some() { "," } else { "" };
+            write!(f, "{} < {}", separator, below)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for Requirement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// What was found when probing a tool
+#[derive(Clone, Debug)]
+pub enum Probe {
+    Missing,
+    /// Found, with its version if one could be read
+    Found(Option<Version>),
+}
+
+/// Probes tools once each by running `tool --version`.
+#[derive(Default)]
+pub struct Toolbox {
+    probes: HashMap<String, Probe>,
+}
+
+impl Toolbox {
+    pub fn probe(&mut self, tool: &str) -> Probe {
+        self.probes
+            .entry(tool.to_string())
+            .or_insert_with(|| match Command::new(tool).arg("--version").output() {
+                Ok(output) => Probe::Found(
+                    Version::find(&String::from_utf8_lossy(&output.stdout))
+                        .or_else(|| Version::find(&String::from_utf8_lossy(&output.stderr))),
+                ),
+                Err(_) => Probe::Missing,
+            })
+            .clone()
+    }
+
+    /// Version of the tool as text
+    pub fn describe(&mut self, tool: &str) -> String {
+        match self.probe(tool) {
+            Probe::Missing => String::from("missing"),
+            Probe::Found(Some(v)) => v.to_string(),
+            Probe::Found(None) => String::from("found, unknown version"),
+        }
+    }
+
+    /// Checks a single requirement. The error says why it isn't met.
+    pub fn check(&mut self, requirement: &Requirement) -> Result<(), String> {
+        let version = match self.probe(&requirement.tool) {
+            Probe::Missing => return Err(format!("{} not found", requirement.tool)),
+            // Can't tell, so give it the benefit of the doubt
+            Probe::Found(None) => return Ok(()),
+            Probe::Found(Some(v)) => v,
+        };
+        let too_old = requirement
+            .min_version
+            .as_ref()
+            .map_or(false, |m| version < *m);
+        let too_new = requirement.below.as_ref().map_or(false, |b| version >= *b);
+        if too_old || too_new {
+            return Err(format!(
+                "{} {} found, {} required",
+                requirement.tool, version, requirement
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks every requirement of the implementation. The error lists the unmet ones.
+    pub fn check_all(&mut self, b: &dyn BFImpl) -> Result<(), String> {
+        let unmet: Vec<String> = b
+            .requirements()
+            .iter()
+            .filter_map(|r| self.check(r).err())
+            .collect();
+        if unmet.is_empty() {
+            Ok(())
+        } else {
+            Err(unmet.join(", "))
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// First line of `--version` of the C and C++ compilers CMake picks by default. Unlike the
+    /// version number alone, it tells compilers of different vendors apart.
+    static ref COMPILER_VERSIONS: BTreeMap<String, String> = [("cc", "CC"), ("c++", "CXX")]
+        .iter()
+        .map(|(tool, variable)| {
+            let compiler = std::env::var(variable).unwrap_or_else(|_| tool.to_string());
+            let version = match Command::new(&compiler).arg("--version").output() {
+                Ok(output) => String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                Err(_) => String::from("missing"),
+            };
+            (tool.to_string(), format!("{}: {}", compiler, version))
+        })
+        .collect();
+}
+
+/// Versions of the C and C++ compilers if the requirements include CMake, which builds with them.
+/// Empty otherwise.
+pub fn compiler_versions(requirements: &[Requirement]) -> BTreeMap<String, String> {
+    if requirements.iter().any(|r| r.tool == "cmake") {
+        COMPILER_VERSIONS.clone()
+    } else {
+        BTreeMap::new()
+    }
+}
+
+/// Prints a matrix of which tools each implementation needs and whether they are usable.
+/// Returns if every implementation is ready.
+pub fn doctor(bf: &[Box<dyn BFImpl + Send + Sync>], backend: TimingBackend) -> bool {
+    let mut toolbox = Toolbox::default();
+
+    let mut tools: Vec<String> = bf
+        .iter()
+        .flat_map(|b| b.requirements())
+        .map(|r| r.tool)
+        .collect();
+    if backend == TimingBackend::Hyperfine {
+        tools.push(String::from("hyperfine"));
+    }
+    tools.sort();
+    tools.dedup();
+
+    println!("Tools:");
+    for tool in &tools {
+        println!("  {:<12} {}", tool, toolbox.describe(tool));
+    }
+
+    let name_width = bf.iter().map(|b| b.name().len()).max().unwrap_or(0);
+    println!(
+        "\n{:<width$}  {}  ready",
+        "",
+        tools.iter().map(|t| format!("{:^9}", t)).join(" "),
+        width = name_width
+    );
+
+    let mut all_ready = true;
+    let mut reasons = Vec::new();
+    for b in bf {
+        let requirements = b.requirements();
+        let cells = tools
+            .iter()
+            .map(|t| {
+                let cell = match requirements.iter().find(|r| &r.tool == t) {
+                    Some(r) => match (toolbox.check(r), toolbox.probe(t)) {
+                        (Ok(()), _) => "ok",
+                        (Err(_), Probe::Missing) => "MISSING",
+                        (Err(_), Probe::Found(_)) => "VERSION",
+                    },
+                    None => "-",
+                };
+                format!("{:^9}", cell)
+            })
+            .join(" ");
+        let ready = toolbox.check_all(&**b);
+        println!(
+            "{:<width$}  {}  {}",
+            b.name(),
+            cells,
+            if ready.is_ok() { "yes" } else { "NO" },
+            width = name_width
+        );
+        if let Err(reason) = ready {
+            all_ready = false;
+            reasons.push(format!("  {}: {}", b.name(), reason));
+        }
+    }
+
+    if backend == TimingBackend::Hyperfine {
+        if let Err(reason) = toolbox.check(&Requirement::tool("hyperfine")) {
+            all_ready = false;
+            reasons.push(format!("  --backend hyperfine: {}", reason));
+        }
+    }
+
+    if !reasons.is_empty() {
+        println!("\nUnmet requirements:\n{}", reasons.join("\n"));
+    }
+    all_ready
+}
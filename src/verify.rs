@@ -0,0 +1,111 @@
+use crate::*;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Location of the golden output for a benchmark. Lives next to the benchmark as `<stem>.out`.
+pub fn expected_output_path(file: &Path) -> PathBuf {
+    file.with_extension("out")
+}
+
+/// Location of the optional stdin contents for a benchmark. Lives next to the benchmark as `<stem>.in`.
+pub fn input_path(file: &Path) -> Option<PathBuf> {
+    let path = file.with_extension("in");
+    if path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Wraps an invoke command so the shell feeds it the given input file on stdin.
+pub fn with_input(command: &str, input: Option<&Path>) -> String {
+    match input {
+        Some(input) => {
+            let input_str = input.to_string_lossy();
+            cfg_if::cfg_if! {
+                if #[cfg(windows)] {
+                    format!("cmd /c \"{} < {}\"", command, input_str)
+                } else {
+                    format!("({}) < {}", command, input_str)
+                }
+            }
+        }
+        None => command.to_string(),
+    }
+}
+
+/// Reads the golden output for a benchmark, if there is one.
+pub fn expected_output(file: &Path) -> Option<Vec<u8>> {
+    std::fs::read(expected_output_path(file)).ok()
+}
+
+/// Runs the implementation once on the benchmark and compares stdout byte-for-byte
+/// against the expected output.
+pub fn verify_output(
+    b: &dyn BFImpl,
+    file: &Path,
+    input: Option<&Path>,
+    expected: &[u8],
+    timeout: Option<Duration>,
+) -> Result<(), Failure> {
+    let failed = |reason| Err(Failure::new(Status::Failed, reason));
+
+    let stdin = match input {
+        Some(input) => match File::open(input) {
+            Ok(f) => Stdio::from(f),
+            Err(e) => return failed(format!("failed to open {}: {}", input.to_string_lossy(), e)),
+        },
+        None => Stdio::null(),
+    };
+
+    let command = b.get_invoke_command(file.to_path_buf());
+    let child = match own_process_group(&mut shell_command(&command))
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) => return failed(format!("failed to launch: {}", e)),
+    };
+
+    let watchdog = Watchdog::start(child.id(), timeout);
+    let output = child.wait_with_output();
+    if Watchdog::stop(watchdog) {
+        return Err(Failure::new(
+            Status::Timeout,
+            format!("timed out after {:.1} s", timeout.unwrap().as_secs_f64()),
+        ));
+    }
+    let output = match output {
+        Ok(o) => o,
+        Err(e) => return failed(format!("failed to wait: {}", e)),
+    };
+
+    if !output.status.success() {
+        return failed(match output.status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => String::from("killed by signal"),
+        });
+    }
+
+    if output.stdout != expected {
+        let first_difference = output
+            .stdout
+            .iter()
+            .zip(expected.iter())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| output.stdout.len().min(expected.len()));
+        return Err(Failure::new(
+            Status::WrongOutput,
+            format!(
+                "expected {} bytes, got {} bytes, first difference at byte {}",
+                expected.len(),
+                output.stdout.len(),
+                first_difference
+            ),
+        ));
+    }
+
+    Ok(())
+}
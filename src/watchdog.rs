@@ -0,0 +1,109 @@
+use crate::*;
+use std::str::FromStr;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// A `--timeout` value. Either `SECONDS` for every benchmark, or `BENCH=SECONDS` for a single one.
+#[derive(Clone, Debug)]
+pub struct TimeoutSpec {
+    /// File name or stem of the benchmark, or None for all benchmarks
+    bench: Option<String>,
+    timeout: Duration,
+}
+
+impl FromStr for TimeoutSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bench, seconds) = match s.rfind('=') {
+            Some(idx) => (Some(s[..idx].to_string()), &s[idx + 1..]),
+            None => (None, s),
+        };
+        let seconds: f64 = seconds
+            .parse()
+            .map_err(|_| format!("invalid timeout {}, expected SECONDS or BENCH=SECONDS", s))?;
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return Err(format!("timeout must be positive, got {}", seconds));
+        }
+        Ok(TimeoutSpec {
+            bench,
+            timeout: Duration::from_secs_f64(seconds),
+        })
+    }
+}
+
+/// Timeout for the given benchmark. Per-benchmark timeouts win over the global one.
+pub fn timeout_for(specs: &[TimeoutSpec], file: &Path) -> Option<Duration> {
+    let name = file.file_name().map(|s| s.to_string_lossy().to_string());
+    let stem = file.file_stem().map(|s| s.to_string_lossy().to_string());
+
+    let specific = specs.iter().rev().find(|s| match &s.bench {
+        Some(b) => Some(b) == name.as_ref() || Some(b) == stem.as_ref(),
+        None => false,
+    });
+    let global = specs.iter().rev().find(|s| s.bench.is_none());
+
+    specific.or(global).map(|s| s.timeout)
+}
+
+/// Puts the command in its own process group, so the whole process tree can be killed at once.
+pub fn own_process_group(c: &mut Command) -> &mut Command {
+    #[cfg(not(windows))]
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        c.pre_exec(|| {
+            libc::setpgid(0, 0);
+            Ok(())
+        });
+    }
+    c
+}
+
+/// Kills the process and all of its children.
+fn kill_tree(pid: u32) {
+    cfg_if::cfg_if! {
+        if #[cfg(windows)] {
+            let _ = Command::new("taskkill")
+                .args(&["/T", "/F", "/PID", &pid.to_string()])
+                .output();
+        } else {
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// Kills a process tree if it is still running once the timeout expires.
+/// The process must have been spawned with `own_process_group`.
+pub struct Watchdog {
+    cancel: Sender<()>,
+    thread: JoinHandle<bool>,
+}
+
+impl Watchdog {
+    pub fn start(pid: u32, timeout: Option<Duration>) -> Option<Self> {
+        let timeout = timeout?;
+        let (cancel, cancelled) = channel();
+        let thread = std::thread::spawn(move || match cancelled.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                kill_tree(pid);
+                true
+            }
+            _ => false,
+        });
+        Some(Watchdog { cancel, thread })
+    }
+
+    /// Stops watching the process. Returns if the process was killed for running too long.
+    pub fn stop(watchdog: Option<Self>) -> bool {
+        match watchdog {
+            Some(w) => {
+                let _ = w.cancel.send(());
+                w.thread.join().unwrap_or(false)
+            }
+            None => false,
+        }
+    }
+}